@@ -0,0 +1,88 @@
+//! Compares the spatial hash broadphase with testing every pair.
+//!
+//! ```sh
+//! cargo bench --bench collisions
+//! ```
+//!
+//! Colliders are bullet- and ship-sized boxes scattered over a 1280x720
+//! playfield. Both methods must report the same pairs.
+
+use std::{hint::black_box, time::Instant};
+
+use alien_space_shooter::SpatialHash;
+use bevy::math::Vec2;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const CELL_SIZE: f32 = 64.0;
+const ITERATIONS: u32 = 20;
+
+fn main() {
+    println!(
+        "{:>9} {:>14} {:>14} {:>8}",
+        "colliders", "pairwise (us)", "grid (us)", "pairs"
+    );
+    for count in [50, 100, 250, 500, 1_000, 2_000, 4_000] {
+        let boxes = random_boxes(count);
+
+        let pairwise = pairwise_pairs(&boxes);
+        let grid = grid_pairs(&boxes);
+        assert_eq!(pairwise, grid, "broadphase disagrees at {count} colliders");
+
+        let pairwise_time = time(|| pairwise_pairs(black_box(&boxes)));
+        let grid_time = time(|| grid_pairs(black_box(&boxes)));
+        println!(
+            "{count:>9} {pairwise_time:>14.1} {grid_time:>14.1} {:>8}",
+            pairwise.len()
+        );
+    }
+}
+
+fn random_boxes(count: usize) -> Vec<(Vec2, Vec2)> {
+    let mut rng = StdRng::seed_from_u64(count as u64);
+    (0..count)
+        .map(|_| {
+            let center = Vec2::new(rng.gen_range(-640.0..640.0), rng.gen_range(-360.0..360.0));
+            let size = if rng.gen_bool(0.9) {
+                Vec2::new(11.0, 11.0)
+            } else {
+                Vec2::new(72.0, 34.0)
+            };
+            (center, size)
+        })
+        .collect()
+}
+
+fn pairwise_pairs(boxes: &[(Vec2, Vec2)]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for (a, (center_a, size_a)) in boxes.iter().enumerate() {
+        for (b, (center_b, size_b)) in boxes.iter().enumerate().skip(a + 1) {
+            let distance = (*center_a - *center_b).abs();
+            if distance.x < size_a.x / 2.0 + size_b.x / 2.0
+                && distance.y < size_a.y / 2.0 + size_b.y / 2.0
+            {
+                pairs.push((a, b));
+            }
+        }
+    }
+    pairs
+}
+
+fn grid_pairs(boxes: &[(Vec2, Vec2)]) -> Vec<(usize, usize)> {
+    let mut grid = SpatialHash::new(CELL_SIZE);
+    for (center, size) in boxes {
+        grid.insert(*center, *size);
+    }
+    let mut pairs = Vec::new();
+    grid.for_each_overlapping_pair(|a, b| pairs.push((a, b)));
+    pairs.sort_unstable();
+    pairs
+}
+
+/// Average microseconds per call.
+fn time<T>(mut f: impl FnMut() -> T) -> f64 {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    start.elapsed().as_secs_f64() * 1e6 / f64::from(ITERATIONS)
+}
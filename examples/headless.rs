@@ -0,0 +1,67 @@
+//! Plays simulated rounds with no window, renderer or audio.
+//!
+//! ```sh
+//! cargo run --example headless -- 100 --seed 1234
+//! cargo run --example headless -- --replay round.replay
+//! ```
+//!
+//! With `--replay` the recorded round is played back once and its score is
+//! printed, which is how a submitted high score can be checked.
+
+use alien_space_shooter::{headless_app, AppState, CurrentScore, GameRng, Replay, ReplayPlayback};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let arg_value = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+    };
+    let seed = arg_value("--seed").and_then(|seed| seed.parse().ok());
+
+    let mut app = headless_app(seed);
+    // The game stays in Loading if a stage is broken, so give up rather
+    // than spin forever.
+    let started = Instant::now();
+    while *app.world.resource::<State<AppState>>().get() == AppState::Loading {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "the game did not finish loading"
+        );
+        app.update();
+    }
+
+    if let Some(path) = arg_value("--replay") {
+        let replay = Replay::read_from(path.as_ref()).expect("could not load replay");
+        app.insert_resource(ReplayPlayback::new(replay));
+        let frames = play_round(&mut app);
+        let score = app.world.resource::<CurrentScore>().value;
+        println!("replay {path}: score {score} after {frames} frames");
+        return;
+    }
+
+    let rounds: u32 = args.get(1).and_then(|arg| arg.parse().ok()).unwrap_or(10);
+    for round in 1..=rounds {
+        let frames = play_round(&mut app);
+        let seed = app.world.resource::<GameRng>().seed();
+        let score = app.world.resource::<CurrentScore>().value;
+        println!("round {round} (seed {seed}): score {score} after {frames} frames");
+    }
+}
+
+fn play_round(app: &mut App) -> u32 {
+    app.world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Game);
+    app.update();
+
+    let mut frames = 0;
+    while *app.world.resource::<State<AppState>>().get() == AppState::Game {
+        app.update();
+        frames += 1;
+    }
+    frames
+}
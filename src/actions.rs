@@ -0,0 +1,323 @@
+//! Named actions, resolved from whichever devices are plugged in.
+//!
+//! Every frame the devices report what they see into `ActionState` during
+//! `ActionSet`. Menus read `ActionState` directly; gameplay reads the
+//! `PlayerInput` built from it each tick, so a replay can stand in for the
+//! devices.
+
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gamepad::{button_label, GamepadBindings},
+    mouse::mouse_label,
+    touch::touch_label,
+};
+
+/// How far the move axis has to go for the move actions to count as
+/// pressed, so an analog stick can work the menus.
+const AXIS_PRESS: f32 = 0.5;
+
+/// Something the player can do, whatever they press to do it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Bomb,
+    Pause,
+    /// Picks the highlighted menu entry, or starts a round from the title.
+    Confirm,
+    Back,
+    /// Opens the high score table from the title screen.
+    Highscores,
+    /// Opens the controls screen from the title screen.
+    Controls,
+}
+
+/// Actions read together on one screen, so they cannot share a key.
+const CONTEXTS: [&[Action]; 3] = [
+    // Playing.
+    &[
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::Bomb,
+        Action::Pause,
+    ],
+    // The pause menu.
+    &[
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::Confirm,
+        Action::Pause,
+    ],
+    // The other menus, where Fire also confirms.
+    &[
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::Fire,
+        Action::Confirm,
+        Action::Back,
+        Action::Highscores,
+        Action::Controls,
+    ],
+];
+
+/// Pairs that do the same thing on every screen that reads both, so they
+/// may share a key.
+const INTERCHANGEABLE: [(Action, Action); 1] = [(Action::Fire, Action::Confirm)];
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::Bomb,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+        Action::Highscores,
+        Action::Controls,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveUp => "MOVE UP",
+            Action::MoveDown => "MOVE DOWN",
+            Action::MoveLeft => "MOVE LEFT",
+            Action::MoveRight => "MOVE RIGHT",
+            Action::Fire => "FIRE",
+            Action::Bomb => "BOMB",
+            Action::Pause => "PAUSE",
+            Action::Confirm => "CONFIRM",
+            Action::Back => "BACK",
+            Action::Highscores => "HIGH SCORES",
+            Action::Controls => "CONTROLS",
+        }
+    }
+
+    /// Whether some screen reads both actions, so one key can't do both.
+    pub fn clashes_with(&self, other: Action) -> bool {
+        *self != other
+            && !INTERCHANGEABLE.contains(&(*self, other))
+            && !INTERCHANGEABLE.contains(&(other, *self))
+            && CONTEXTS
+                .iter()
+                .any(|context| context.contains(self) && context.contains(&other))
+    }
+}
+
+/// What every action is doing this frame.
+#[derive(Resource, Debug, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    previous: HashSet<Action>,
+    move_axis: Vec2,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// Pressed this frame but not the one before.
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.pressed(action) && !self.previous.contains(&action)
+    }
+
+    /// Where the player is steering, from -1.0 to 1.0 on each axis. The
+    /// move actions count as full deflection.
+    pub fn move_axis(&self) -> Vec2 {
+        self.move_axis
+    }
+
+    /// Called by a device to report a held action.
+    pub fn press(&mut self, action: Action) {
+        self.pressed.insert(action);
+    }
+
+    /// Called by a device to report analog steering.
+    pub fn push_axis(&mut self, axis: Vec2) {
+        self.move_axis += axis;
+    }
+}
+
+/// Which keys trigger each action.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBindings {
+    pub keys: HashMap<Action, Vec<KeyCode>>,
+}
+
+impl KeyBindings {
+    /// An action bound to `key` that `action` can't share it with.
+    pub fn clash(&self, action: Action, key: KeyCode) -> Option<Action> {
+        Action::ALL.into_iter().find(|other| {
+            action.clashes_with(*other)
+                && self.keys.get(other).is_some_and(|keys| keys.contains(&key))
+        })
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let keys = [
+            (Action::MoveUp, vec![KeyCode::ArrowUp]),
+            (Action::MoveDown, vec![KeyCode::ArrowDown]),
+            (Action::MoveLeft, vec![KeyCode::ArrowLeft]),
+            (Action::MoveRight, vec![KeyCode::ArrowRight]),
+            (Action::Fire, vec![KeyCode::KeyF]),
+            (Action::Bomb, vec![KeyCode::KeyB]),
+            (Action::Pause, vec![KeyCode::Escape, KeyCode::KeyP]),
+            (Action::Confirm, vec![KeyCode::KeyF, KeyCode::Enter]),
+            (Action::Back, vec![KeyCode::Escape]),
+            (Action::Highscores, vec![KeyCode::KeyH]),
+            (Action::Controls, vec![KeyCode::KeyC]),
+        ];
+        Self {
+            keys: keys.into_iter().collect(),
+        }
+    }
+}
+
+/// The kind of device the player last used, for naming buttons on screen.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputDevice {
+    #[default]
+    Keyboard,
+    Gamepad,
+    Touch,
+    /// Steering by following the mouse.
+    Mouse,
+}
+
+/// Names the button for an action on whichever device is in use.
+#[derive(SystemParam)]
+pub struct Prompts<'w> {
+    device: Res<'w, InputDevice>,
+    keys: Res<'w, KeyBindings>,
+    buttons: Res<'w, GamepadBindings>,
+}
+
+impl Prompts<'_> {
+    pub fn device(&self) -> InputDevice {
+        *self.device
+    }
+
+    /// The first button bound to `action`, like `F` or `A`.
+    pub fn label(&self, action: Action) -> String {
+        let key = || {
+            self.keys
+                .keys
+                .get(&action)
+                .and_then(|keys| keys.first())
+                .map(|key| key_label(*key))
+        };
+        let label = match *self.device {
+            InputDevice::Keyboard => key(),
+            InputDevice::Gamepad => self
+                .buttons
+                .buttons
+                .get(&action)
+                .and_then(|buttons| buttons.first())
+                .map(|button| button_label(*button).to_string()),
+            InputDevice::Touch => Some(touch_label(action).to_string()),
+            // Anything without a mouse button is still on the keyboard.
+            InputDevice::Mouse => mouse_label(action).map(str::to_string).or_else(key),
+        };
+        label.unwrap_or_else(|| "-".to_string())
+    }
+}
+
+/// A short upper case name for a key, like `F`, `UP` or `ENTER`.
+pub fn key_label(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    let name = ["Key", "Digit", "Arrow"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(&name);
+    name.to_uppercase()
+}
+
+/// Devices report into `ActionState` in this set, in `PreUpdate`.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActionSet;
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActionState>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<InputDevice>()
+            .configure_sets(PreUpdate, ActionSet.after(bevy::input::InputSystem))
+            .add_systems(
+                PreUpdate,
+                (
+                    begin_frame.before(ActionSet),
+                    read_keyboard.in_set(ActionSet),
+                    end_frame.after(ActionSet),
+                ),
+            );
+    }
+}
+
+fn begin_frame(mut state: ResMut<ActionState>) {
+    let state = &mut *state;
+    std::mem::swap(&mut state.previous, &mut state.pressed);
+    state.pressed.clear();
+    state.move_axis = Vec2::ZERO;
+}
+
+fn read_keyboard(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut state: ResMut<ActionState>,
+    mut device: ResMut<InputDevice>,
+) {
+    if keyboard_input.get_just_pressed().next().is_some() {
+        device.set_if_neq(InputDevice::Keyboard);
+    }
+    for (action, keys) in bindings.keys.iter() {
+        if keyboard_input.any_pressed(keys.iter().copied()) {
+            state.press(*action);
+        }
+    }
+}
+
+fn end_frame(mut state: ResMut<ActionState>) {
+    let mut digital = Vec2::ZERO;
+    for (action, direction) in [
+        (Action::MoveUp, Vec2::Y),
+        (Action::MoveDown, Vec2::NEG_Y),
+        (Action::MoveLeft, Vec2::NEG_X),
+        (Action::MoveRight, Vec2::X),
+    ] {
+        if state.pressed(action) {
+            digital += direction;
+        }
+    }
+    let axis = (state.move_axis + digital).clamp(Vec2::NEG_ONE, Vec2::ONE);
+    state.move_axis = axis;
+
+    for (action, pushed) in [
+        (Action::MoveUp, axis.y),
+        (Action::MoveDown, -axis.y),
+        (Action::MoveLeft, -axis.x),
+        (Action::MoveRight, axis.x),
+    ] {
+        if pushed > AXIS_PRESS {
+            state.press(action);
+        }
+    }
+}
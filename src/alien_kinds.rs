@@ -0,0 +1,171 @@
+use std::{fmt, str::FromStr};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::bullet_patterns::BulletPattern;
+
+/// Every kind of alien, each with its own movement, toughness and guns.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum AlienKind {
+    /// Flies straight across the screen. The original alien.
+    Drifter,
+    /// Bobs up and down on a sine wave.
+    Weaver,
+    /// Dives at the player and never shoots.
+    Kamikaze,
+    /// Flies in, stops to fire spreads, then leaves.
+    Turret,
+    /// Bounces diagonally between two heights.
+    Zigzag,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlienStats {
+    pub health: u32,
+    pub score: u32,
+    /// Leftward speed is picked from this range at spawn.
+    pub speed: (f32, f32),
+    /// What each volley looks like. Kinds without one never shoot.
+    pub fire: Option<BulletPattern>,
+    /// Frames between shots, picked at random up to this value.
+    pub reload: f32,
+    /// Tints the shared alien sprite so kinds can be told apart.
+    pub color: Color,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownAlienKind(pub String);
+
+impl fmt::Display for UnknownAlienKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no alien kind is called {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownAlienKind {}
+
+impl AlienKind {
+    pub const ALL: [AlienKind; 5] = [
+        AlienKind::Drifter,
+        AlienKind::Weaver,
+        AlienKind::Kamikaze,
+        AlienKind::Turret,
+        AlienKind::Zigzag,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlienKind::Drifter => "drifter",
+            AlienKind::Weaver => "weaver",
+            AlienKind::Kamikaze => "kamikaze",
+            AlienKind::Turret => "turret",
+            AlienKind::Zigzag => "zigzag",
+        }
+    }
+
+    pub fn stats(&self) -> AlienStats {
+        match self {
+            AlienKind::Drifter => AlienStats {
+                health: 1,
+                score: 1,
+                speed: (100.0, 350.0),
+                fire: Some(BulletPattern::Aimed),
+                reload: 180.0,
+                color: Color::WHITE,
+            },
+            AlienKind::Weaver => AlienStats {
+                health: 2,
+                score: 2,
+                speed: (120.0, 180.0),
+                fire: Some(BulletPattern::Leading),
+                reload: 150.0,
+                color: Color::rgb(0.6, 1.0, 0.6),
+            },
+            AlienKind::Kamikaze => AlienStats {
+                health: 1,
+                score: 3,
+                speed: (280.0, 340.0),
+                fire: None,
+                reload: 0.0,
+                color: Color::rgb(1.0, 0.5, 0.4),
+            },
+            AlienKind::Turret => AlienStats {
+                health: 4,
+                score: 5,
+                speed: (180.0, 220.0),
+                fire: Some(BulletPattern::Fan {
+                    count: 5,
+                    angle: 0.2,
+                }),
+                reload: 90.0,
+                color: Color::rgb(0.6, 0.7, 1.0),
+            },
+            AlienKind::Zigzag => AlienStats {
+                health: 2,
+                score: 2,
+                speed: (180.0, 240.0),
+                fire: Some(BulletPattern::Burst {
+                    count: 3,
+                    interval: 0.15,
+                }),
+                reload: 200.0,
+                color: Color::rgb(1.0, 1.0, 0.5),
+            },
+        }
+    }
+}
+
+impl fmt::Display for AlienKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for AlienKind {
+    type Err = UnknownAlienKind;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        AlienKind::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| UnknownAlienKind(name.to_string()))
+    }
+}
+
+/// Per-alien movement state, driven by `steer_aliens` before each move.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub enum Behaviour {
+    Straight,
+    Weave {
+        age: f32,
+    },
+    Kamikaze,
+    Turret {
+        stop_x: f32,
+        hold: f32,
+        parked: bool,
+    },
+    Zigzag {
+        until_flip: f32,
+    },
+}
+
+impl Behaviour {
+    /// Turrets only shoot while parked.
+    pub fn can_fire(&self) -> bool {
+        match *self {
+            Behaviour::Turret { hold, parked, .. } => parked && hold > 0.0,
+            _ => true,
+        }
+    }
+}
+
+pub const WEAVE_AMPLITUDE: f32 = 80.0;
+/// Radians per second.
+pub const WEAVE_FREQUENCY: f32 = 2.5;
+pub const KAMIKAZE_TURN_RATE: f32 = 3.0;
+pub const TURRET_HOLD: f32 = 4.0;
+pub const TURRET_LEAVE_SPEED: f32 = 150.0;
+pub const ZIGZAG_PERIOD: f32 = 0.6;
+pub const ZIGZAG_SPEED: f32 = 200.0;
@@ -1,20 +1,24 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
+use rand::Rng;
 
 use crate::{
-    collisions::Collider,
-    explosions::Explosion,
-    fighter::{IsBullet, Player, Reload, Team},
+    alien_kinds::{
+        AlienKind, Behaviour, KAMIKAZE_TURN_RATE, TURRET_HOLD, TURRET_LEAVE_SPEED, WEAVE_AMPLITUDE,
+        WEAVE_FREQUENCY, ZIGZAG_PERIOD, ZIGZAG_SPEED,
+    },
+    bullet_patterns::{fire_pattern, VolleyCount},
+    collisions::{Collider, CollisionEvent, CollisionLayers, CollisionPhase, Layer},
+    explosions::{spawn_explosion, Explosion},
+    fighter::{Player, PlayerVelocity, Reload},
+    health::{apply_damage, deal_damage, Damage, Health, HitFlash, Killed, ScoreValue},
     hud::CurrentScore,
-    movement::{Position, Velocity},
-    scene::{SceneAssets, SceneSounds, Size},
-    AppState,
+    movement::{update_position, Position, Velocity},
+    pickups::roll_drop,
+    rng::GameRng,
+    scene::{PlayfieldBounds, SceneAssets, SceneSounds, Size},
+    AppState, GameplaySet,
 };
 
-#[derive(Resource, Debug, Default)]
-pub struct AlienRespawnTimer {
-    value: f32,
-}
-
 #[derive(Component, Debug)]
 pub struct Alien;
 
@@ -25,179 +29,375 @@ pub struct AliensPlugin;
 
 impl Plugin for AliensPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<AlienRespawnTimer>().add_systems(
-            Update,
+        app.add_event::<SpawnAlien>().add_systems(
+            FixedUpdate,
             (
-                spawn_aliens,
-                spawn_alien_bullets,
-                handle_alien_collisions,
-                handle_alien_bullet_collisions,
+                // Chained so both draw from the gameplay stream in a fixed order.
+                (spawn_requested_aliens, spawn_alien_bullets)
+                    .chain()
+                    .in_set(GameplaySet::Spawn),
+                steer_aliens
+                    .in_set(GameplaySet::Movement)
+                    .before(update_position),
+                destroy_rammers
+                    .in_set(GameplaySet::Damage)
+                    .after(apply_damage),
+                (handle_alien_deaths, handle_alien_bullet_collisions)
+                    .in_set(GameplaySet::CollisionResponse),
             )
                 .run_if(in_state(AppState::Game)),
         );
     }
 }
 
-fn spawn_aliens(
-    time: Res<Time>,
+/// Asks for an alien of `kind` at `position`, for example from
+/// `"weaver".parse::<AlienKind>()`. Handled during `GameplaySet::Spawn`.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SpawnAlien {
+    pub kind: AlienKind,
+    pub position: Vec2,
+}
+
+pub(crate) fn spawn_requested_aliens(
     mut commands: Commands,
-    window: Query<&Window>,
-    mut spawn_timer: ResMut<AlienRespawnTimer>,
+    mut spawn_events: EventReader<SpawnAlien>,
+    bounds: Res<PlayfieldBounds>,
     scene_assets: Res<SceneAssets>,
+    mut rng: ResMut<GameRng>,
 ) {
-    spawn_timer.value -= 60.0 * time.delta_seconds();
-    if spawn_timer.value <= 0.0 {
-        let window = window.single();
-        let alien_pos_x = window.width() / 2.0;
-        let alien_pos_y: f32 = (rand::random::<f32>() * window.height()) - window.height() / 2.0;
-        let alien_speed: f32 = (rand::random::<f32>() * 250.) + 100.;
-        commands.spawn((
+    for request in spawn_events.read() {
+        let stats = request.kind.stats();
+        let speed = rng.gameplay.gen_range(stats.speed.0..=stats.speed.1);
+        let mut position = request.position;
+        let mut velocity = Vec2::new(-speed, 0.0);
+        let behaviour = match request.kind {
+            AlienKind::Drifter => Behaviour::Straight,
+            AlienKind::Weaver => {
+                // Keep the whole wave on screen.
+                let limit = (bounds.height / 2.0 - WEAVE_AMPLITUDE).max(0.0);
+                position.y = position.y.clamp(-limit, limit);
+                Behaviour::Weave { age: 0.0 }
+            }
+            AlienKind::Kamikaze => Behaviour::Kamikaze,
+            AlienKind::Turret => Behaviour::Turret {
+                stop_x: rng.gameplay.gen_range(0.15..0.35) * bounds.width,
+                hold: TURRET_HOLD,
+                parked: false,
+            },
+            AlienKind::Zigzag => {
+                velocity.y = if rng.gameplay.gen_bool(0.5) {
+                    ZIGZAG_SPEED
+                } else {
+                    -ZIGZAG_SPEED
+                };
+                Behaviour::Zigzag {
+                    until_flip: ZIGZAG_PERIOD / 2.0,
+                }
+            }
+        };
+
+        let position = position.extend(0.0);
+        // Kamikazes hurt by flying into the player.
+        let rams = request.kind == AlienKind::Kamikaze;
+        let mut filter = Layer::PLAYER_SHOT | Layer::HAZARD;
+        if rams {
+            filter |= Layer::PLAYER;
+        }
+        let mut alien = commands.spawn((
             SpriteBundle {
+                sprite: Sprite {
+                    color: stats.color,
+                    ..default()
+                },
                 texture: scene_assets.alien.image.clone_weak(),
-                transform: Transform::from_xyz(alien_pos_x, alien_pos_y, 0.0),
+                transform: Transform::from_translation(position),
                 ..default()
             },
-            Position::new(Vec3::new(alien_pos_x, alien_pos_y, 0.)),
-            Velocity::new(Vec3::new(-alien_speed, 0.0, 0.0)),
-            Reload::new(rand::random::<f32>() * 120.0),
+            Position::new(position),
+            Velocity::new(velocity.extend(0.0)),
+            Reload::new(rng.gameplay.gen::<f32>() * 120.0),
+            VolleyCount::default(),
             Alien,
-            Collider::new(Size::new(scene_assets.alien.dimensions)),
+            request.kind,
+            behaviour,
+            Collider::capsule_in(scene_assets.alien.size())
+                .with_alpha_mask(scene_assets.alien.image.clone_weak()),
+            Health::new(stats.health),
+            ScoreValue::new(stats.score),
             Size::new(scene_assets.alien.dimensions),
-            Team::new(0),
-            IsBullet::new(false),
+            CollisionLayers::new(Layer::ENEMY, filter),
         ));
-        spawn_timer.value = rand::random::<f32>() * 150.0;
+        if rams {
+            alien.insert(Damage::new(RAM_DAMAGE));
+        }
     }
 }
 
-const ALIEN_BULLET_SPEED: f32 = 300.0;
-
-fn spawn_alien_bullets(
-    time: Res<Time>,
+/// A kamikaze that hits the player is destroyed with it.
+#[allow(clippy::type_complexity)]
+fn destroy_rammers(
     mut commands: Commands,
-    player_query: Query<&Position, With<Player>>,
-    mut aliens_query: Query<(&Position, &mut Reload), With<Alien>>,
-    scene_assets: Res<SceneAssets>,
-    scene_sounds: Res<SceneSounds>,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut rammers_query: Query<(&mut Health, Option<&mut HitFlash>), (With<Alien>, With<Damage>)>,
+    player_query: Query<(), With<Player>>,
+    mut killed_events: EventWriter<Killed>,
 ) {
-    if let Ok(player_position) = player_query.get_single() {
-        for (position, mut reload) in aliens_query.iter_mut() {
-            reload.value -= 60.0 * time.delta_seconds();
-
-            if reload.value <= 0.0 {
-                commands.spawn((
-                    SpriteBundle {
-                        texture: scene_assets.alien_bullet.image.clone_weak(),
-                        transform: Transform::from_xyz(
-                            position.value.x,
-                            position.value.y,
-                            position.value.z,
-                        ),
-                        ..default()
-                    },
-                    AlienBullet,
-                    Velocity::new(
-                        calculate_slope(&position.value, &player_position.value)
-                            * ALIEN_BULLET_SPEED,
-                    ),
-                    Position::new(position.value),
-                    Collider::new(Size::new(scene_assets.alien_bullet.dimensions)),
-                    Size::new(scene_assets.alien_bullet.dimensions),
-                    Team::new(0),
-                    IsBullet::new(true),
-                ));
-                reload.value = rand::random::<f32>() * 180.0;
-
-                commands.spawn(AudioBundle {
-                    source: scene_sounds.alien_fire.clone(),
-                    settings: PlaybackSettings {
-                        mode: bevy::audio::PlaybackMode::Remove,
-                        ..default()
-                    },
-                    ..default()
-                });
+    for event in collision_events.read() {
+        if event.phase != CollisionPhase::Enter {
+            continue;
+        }
+        for (entity, other) in event.pairs() {
+            if !player_query.contains(other) {
+                continue;
             }
+            let Ok((mut health, flash)) = rammers_query.get_mut(entity) else {
+                continue;
+            };
+            let amount = health.current;
+            deal_damage(
+                &mut commands,
+                &mut killed_events,
+                (entity, &mut health, flash),
+                other,
+                amount,
+            );
         }
     }
 }
 
-fn calculate_slope(position_from: &Vec3, position_to: &Vec3) -> Vec3 {
-    let steps = (position_to.x - position_from.x)
-        .abs()
-        .max((position_to.y - position_from.y).abs());
-    if steps == 0.0 {
-        return Vec3::new(0.0, 0.0, 0.0);
+fn steer_aliens(
+    time: Res<Time>,
+    player_query: Query<&Position, With<Player>>,
+    mut query: Query<(&mut Behaviour, &mut Velocity, &Position), With<Alien>>,
+) {
+    let delta = time.delta_seconds();
+    let player_position = player_query.get_single().ok();
+    for (mut behaviour, mut velocity, position) in query.iter_mut() {
+        match &mut *behaviour {
+            Behaviour::Straight => {}
+            Behaviour::Weave { age } => {
+                *age += delta;
+                velocity.value.y =
+                    WEAVE_AMPLITUDE * WEAVE_FREQUENCY * (WEAVE_FREQUENCY * *age).cos();
+            }
+            Behaviour::Kamikaze => {
+                // Only home in while the player is still ahead, so a missed
+                // dive carries on off screen.
+                let Some(target) = player_position.filter(|p| p.value.x < position.value.x) else {
+                    continue;
+                };
+                let speed = velocity.value.length();
+                let wanted = (target.value - position.value).normalize_or_zero() * speed;
+                let turned = velocity
+                    .value
+                    .lerp(wanted, (KAMIKAZE_TURN_RATE * delta).min(1.0));
+                velocity.value = turned.normalize_or_zero() * speed;
+            }
+            Behaviour::Turret {
+                stop_x,
+                hold,
+                parked,
+            } => {
+                if !*parked && position.value.x <= *stop_x {
+                    *parked = true;
+                    velocity.value = Vec3::ZERO;
+                } else if *parked && *hold > 0.0 {
+                    *hold -= delta;
+                    if *hold <= 0.0 {
+                        velocity.value = Vec3::new(-TURRET_LEAVE_SPEED, 0.0, 0.0);
+                    }
+                }
+            }
+            Behaviour::Zigzag { until_flip } => {
+                *until_flip -= delta;
+                if *until_flip <= 0.0 {
+                    velocity.value.y = -velocity.value.y;
+                    *until_flip += ZIGZAG_PERIOD;
+                }
+            }
+        }
     }
-    (position_to.clone() - position_from.clone()) / steps
 }
 
-fn handle_alien_collisions(
+const RAM_DAMAGE: u32 = 1;
+pub(crate) const ALIEN_BULLET_SPEED: f32 = 300.0;
+const ALIEN_BULLET_DAMAGE: u32 = 1;
+
+#[allow(clippy::type_complexity)]
+fn spawn_alien_bullets(
+    time: Res<Time>,
     mut commands: Commands,
-    query: Query<(Entity, &Collider, &Position), With<Alien>>,
-    scene_sounds: Res<SceneSounds>,
+    player_query: Query<(&Position, &PlayerVelocity), With<Player>>,
+    mut aliens_query: Query<
+        (
+            Entity,
+            &Position,
+            &mut Reload,
+            &mut VolleyCount,
+            &AlienKind,
+            &Behaviour,
+        ),
+        With<Alien>,
+    >,
     scene_assets: Res<SceneAssets>,
-    mut score: ResMut<CurrentScore>,
+    scene_sounds: Res<SceneSounds>,
+    mut rng: ResMut<GameRng>,
 ) {
-    for (entity, collider, position) in query.iter() {
-        for &collided_entity in collider.colliding_entities.iter() {
-            // Asteroid collided with another asteroid.
-            if query.get(collided_entity).is_ok() {
-                continue;
-            }
+    if let Ok((player_position, player_velocity)) = player_query.get_single() {
+        for (entity, position, mut reload, mut volleys, kind, behaviour) in aliens_query.iter_mut()
+        {
+            reload.value -= 60.0 * time.delta_seconds();
 
-            for _ in 0..30 {
-                let explosion = Explosion::new(position.value.x, position.value.y);
-
-                let image = scene_assets.explosion.image.clone();
-
-                commands.spawn((
-                    SpriteBundle {
-                        sprite: Sprite {
-                            color: Color::Rgba {
-                                red: explosion.r,
-                                green: explosion.g,
-                                blue: explosion.b,
-                                alpha: explosion.a,
-                            },
-                            ..default()
-                        },
-                        texture: image,
-                        transform: Transform::from_xyz(explosion.x, explosion.y, 0.0),
-                        ..default()
-                    },
-                    Velocity::new(Vec3::new(explosion.dx, explosion.dy, 0.0)),
-                    Position::new(Vec3::new(explosion.x, explosion.y, 0.0)),
-                    explosion,
-                ));
+            let stats = kind.stats();
+            if reload.value > 0.0 || !behaviour.can_fire() {
+                continue;
             }
-            // Despawn the alien.
-            commands.entity(entity).despawn_recursive();
+            let Some(pattern) = stats.fire else {
+                continue;
+            };
+            fire_pattern(
+                &mut commands,
+                &scene_assets,
+                pattern,
+                entity,
+                position.value,
+                player_position.value,
+                player_velocity.value,
+                volleys.0,
+            );
+            volleys.0 += 1;
+            reload.value = rng.gameplay.gen::<f32>() * stats.reload;
 
             commands.spawn(AudioBundle {
-                source: scene_sounds.alien_dies.clone(),
+                source: scene_sounds.alien_fire.clone(),
                 settings: PlaybackSettings {
                     mode: bevy::audio::PlaybackMode::Remove,
                     ..default()
                 },
-                ..default()
             });
-            score.value += 1;
         }
     }
 }
 
+pub(crate) fn spawn_alien_bullet(
+    commands: &mut Commands,
+    scene_assets: &SceneAssets,
+    position: Vec3,
+    velocity: Vec2,
+) {
+    commands.spawn((
+        SpriteBundle {
+            texture: scene_assets.alien_bullet.image.clone_weak(),
+            transform: Transform::from_translation(position),
+            ..default()
+        },
+        AlienBullet,
+        Velocity::new(velocity.extend(0.0)),
+        Position::new(position),
+        Collider::capsule_in(scene_assets.alien_bullet.size()),
+        Damage::new(ALIEN_BULLET_DAMAGE),
+        Size::new(scene_assets.alien_bullet.dimensions),
+        CollisionLayers::new(Layer::ENEMY_SHOT, Layer::PLAYER | Layer::HAZARD),
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_alien_deaths(
+    mut commands: Commands,
+    mut killed_events: EventReader<Killed>,
+    query: Query<(&Position, &ScoreValue), With<Alien>>,
+    scene_sounds: Res<SceneSounds>,
+    scene_assets: Res<SceneAssets>,
+    mut score: ResMut<CurrentScore>,
+    mut rng: ResMut<GameRng>,
+    asset_server: Res<AssetServer>,
+) {
+    for killed in killed_events.read() {
+        let Ok((position, score_value)) = query.get(killed.entity) else {
+            continue;
+        };
+        roll_drop(
+            &mut commands,
+            &mut rng.gameplay,
+            &asset_server,
+            position.value,
+        );
+        for _ in 0..30 {
+            let explosion = Explosion::new(position.value.x, position.value.y, &mut rng.cosmetic);
+            spawn_explosion(&mut commands, &scene_assets, explosion);
+        }
+        // Despawn the alien.
+        commands.entity(killed.entity).despawn_recursive();
+
+        commands.spawn(AudioBundle {
+            source: scene_sounds.alien_dies.clone(),
+            settings: PlaybackSettings {
+                mode: bevy::audio::PlaybackMode::Remove,
+                ..default()
+            },
+            ..default()
+        });
+        score.value += score_value.value;
+    }
+}
+
 fn handle_alien_bullet_collisions(
     mut commands: Commands,
-    query: Query<(Entity, &Collider), With<AlienBullet>>,
+    mut collision_events: EventReader<CollisionEvent>,
+    query: Query<(), With<AlienBullet>>,
 ) {
-    for (entity, collider) in query.iter() {
-        for &collided_entity in collider.colliding_entities.iter() {
-            // Asteroid collided with another asteroid.
-            if query.get(collided_entity).is_ok() {
-                continue;
+    let mut spent = HashSet::new();
+    for event in collision_events.read() {
+        if event.phase != CollisionPhase::Enter {
+            continue;
+        }
+        for (entity, _) in event.pairs() {
+            if query.contains(entity) && spent.insert(entity) {
+                commands.entity(entity).despawn_recursive();
             }
-            // Despawn the asteroid.
-            commands.entity(entity).despawn_recursive();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fighter::Lives, headless_app};
+
+    #[test]
+    fn kamikaze_that_reaches_the_player_costs_a_life() {
+        let mut app = headless_app(Some(1));
+        while *app.world.resource::<State<AppState>>().get() == AppState::Loading {
+            app.update();
+        }
+        app.world
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Game);
+        app.update();
+
+        let player = app
+            .world
+            .query_filtered::<&Position, With<Player>>()
+            .single(&app.world)
+            .value
+            .truncate();
+        app.world.send_event(SpawnAlien {
+            kind: AlienKind::Kamikaze,
+            position: player + Vec2::new(150.0, 0.0),
+        });
+        let lives = app.world.resource::<Lives>().remaining;
+
+        // Well before the stage's own aliens could reach the player.
+        for _ in 0..60 {
+            app.update();
+        }
+        assert_eq!(app.world.resource::<Lives>().remaining, lives - 1);
+        let kamikazes = app
+            .world
+            .query::<&AlienKind>()
+            .iter(&app.world)
+            .filter(|kind| **kind == AlienKind::Kamikaze)
+            .count();
+        assert_eq!(kamikazes, 0);
+    }
+}
@@ -0,0 +1,118 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    aliens::{Alien, AlienBullet},
+    explosions::{spawn_explosion, Explosion},
+    fighter::{Invulnerable, Player, PlayerInput},
+    health::{apply_damage, deal_damage, Health, HitFlash, Killed},
+    movement::Position,
+    rng::GameRng,
+    scene::{SceneAssets, SceneSounds},
+    AppState, GameplaySet,
+};
+
+const STARTING_BOMBS: u32 = 2;
+pub const MAX_BOMBS: u32 = 5;
+/// Taken off every alien on screen, so small ones die and the boss doesn't.
+const BOMB_DAMAGE: u32 = 10;
+const BOMB_INVULNERABILITY: f32 = 1.5;
+const SHOCKWAVE_PARTICLES: u32 = 120;
+
+/// Smart bombs left in the current round.
+#[derive(Resource, Debug, Default)]
+pub struct Bombs {
+    pub remaining: u32,
+    /// Whether the bomb button was down last tick. Taken from the player's
+    /// input rather than the device, so replays detonate on the same ticks.
+    held: bool,
+}
+
+pub struct BombsPlugin;
+
+impl Plugin for BombsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Bombs>()
+            .add_systems(OnEnter(AppState::Game), reset_bombs)
+            .add_systems(
+                FixedUpdate,
+                detonate_bomb
+                    .in_set(GameplaySet::Damage)
+                    .after(apply_damage)
+                    .run_if(in_state(AppState::Game)),
+            );
+    }
+}
+
+fn reset_bombs(mut bombs: ResMut<Bombs>) {
+    *bombs = Bombs {
+        remaining: STARTING_BOMBS,
+        held: false,
+    };
+}
+
+/// Clears every alien bullet and hurts every alien when the bomb button is
+/// pressed, leaving the player briefly invulnerable.
+#[allow(clippy::too_many_arguments)]
+fn detonate_bomb(
+    mut commands: Commands,
+    player_input: Res<PlayerInput>,
+    mut bombs: ResMut<Bombs>,
+    mut player_query: Query<(Entity, &Position, Option<&mut Invulnerable>), With<Player>>,
+    bullets_query: Query<Entity, With<AlienBullet>>,
+    mut aliens_query: Query<(Entity, &mut Health, Option<&mut HitFlash>), With<Alien>>,
+    mut killed_events: EventWriter<Killed>,
+    mut rng: ResMut<GameRng>,
+    scene_assets: Res<SceneAssets>,
+    scene_sounds: Res<SceneSounds>,
+) {
+    // One bomb per press, however long the button is held.
+    let pressed = player_input.bomb && !bombs.held;
+    bombs.held = player_input.bomb;
+    if !pressed || bombs.remaining == 0 {
+        return;
+    }
+    let Ok((player, position, invulnerable)) = player_query.get_single_mut() else {
+        return;
+    };
+    bombs.remaining -= 1;
+
+    for bullet in bullets_query.iter() {
+        commands.entity(bullet).despawn_recursive();
+    }
+    for (alien, mut health, flash) in aliens_query.iter_mut() {
+        deal_damage(
+            &mut commands,
+            &mut killed_events,
+            (alien, &mut health, flash),
+            player,
+            BOMB_DAMAGE,
+        );
+    }
+
+    match invulnerable {
+        Some(mut invulnerable) => {
+            invulnerable.remaining = invulnerable.remaining.max(BOMB_INVULNERABILITY);
+        }
+        None => {
+            commands.entity(player).insert(Invulnerable {
+                remaining: BOMB_INVULNERABILITY,
+            });
+        }
+    }
+
+    for i in 0..SHOCKWAVE_PARTICLES {
+        let angle = i as f32 * TAU / SHOCKWAVE_PARTICLES as f32;
+        let explosion =
+            Explosion::shockwave(position.value.x, position.value.y, angle, &mut rng.cosmetic);
+        spawn_explosion(&mut commands, &scene_assets, explosion);
+    }
+    commands.spawn(AudioBundle {
+        source: scene_sounds.alien_dies.clone(),
+        settings: PlaybackSettings {
+            mode: bevy::audio::PlaybackMode::Remove,
+            ..default()
+        },
+    });
+}
@@ -0,0 +1,424 @@
+use bevy::prelude::*;
+
+use crate::{
+    aliens::Alien,
+    bullet_patterns::{fire_pattern, BulletPattern},
+    collisions::{Collider, CollisionLayers, Layer},
+    fighter::{Player, PlayerVelocity},
+    health::{Health, HitFlash, Killed, ScoreValue},
+    movement::{update_position, Position},
+    scene::{PlayfieldBounds, SceneAssets, SceneSounds},
+    AppState, GameplaySet,
+};
+
+const CORE_SCALE: f32 = 2.5;
+const CORE_HEALTH: u32 = 60;
+/// Awarded on top of the parts' own scores when the core is destroyed.
+const BOSS_BONUS: u32 = 100;
+const CANNON_HEALTH: u32 = 15;
+const CANNON_SCORE: u32 = 10;
+const CANNON_OFFSET: Vec2 = Vec2::new(-30.0, 75.0);
+const ENTRY_SPEED: f32 = 120.0;
+const BOB_AMPLITUDE: f32 = 120.0;
+/// Radians per second.
+const BOB_FREQUENCY: f32 = 0.8;
+/// Health fractions at which the boss moves on to its next phase.
+const PHASE_THRESHOLDS: [f32; 2] = [0.66, 0.33];
+const PHASE_COLORS: [Color; 3] = [
+    Color::WHITE,
+    Color::rgb(1.0, 0.75, 0.5),
+    Color::rgb(1.0, 0.45, 0.45),
+];
+const CANNON_PATTERN: BulletPattern = BulletPattern::Fan {
+    count: 3,
+    angle: 0.2,
+};
+const RING_PATTERN: BulletPattern = BulletPattern::Ring { count: 12 };
+const SPIRAL_PATTERN: BulletPattern = BulletPattern::Spiral {
+    arms: 2,
+    turn: 0.35,
+};
+
+/// Asks for the boss to fly in. Sent by a stage's `Boss` action.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SpawnBoss;
+
+/// The invisible anchor every part of the boss moves with.
+#[derive(Component, Debug)]
+pub struct Boss {
+    pub phase: usize,
+    /// Starting health of every part together, for the health bar.
+    total_health: u32,
+    age: f32,
+    stop_x: f32,
+    parked_at: Option<f32>,
+    cannon_reload: f32,
+    core_reload: f32,
+    core_volleys: u32,
+}
+
+#[derive(Component, Debug)]
+pub struct BossPart {
+    pub boss: Entity,
+    pub offset: Vec2,
+}
+
+/// Killing this part kills the whole boss.
+#[derive(Component, Debug)]
+pub struct BossCore;
+
+#[derive(Component, Debug)]
+pub struct BossCannon;
+
+#[derive(Component, Debug)]
+struct BossHealthBar;
+
+#[derive(Component, Debug)]
+struct BossHealthFill;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnBoss>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    (spawn_boss, boss_attacks)
+                        .chain()
+                        .in_set(GameplaySet::Spawn),
+                    move_boss
+                        .in_set(GameplaySet::Movement)
+                        .after(update_position),
+                    (update_phase, handle_boss_death).in_set(GameplaySet::CollisionResponse),
+                )
+                    .run_if(in_state(AppState::Game)),
+            )
+            .add_systems(Update, update_health_bar.run_if(in_state(AppState::Game)))
+            .add_systems(OnExit(AppState::Game), despawn_boss);
+    }
+}
+
+fn spawn_boss(
+    mut commands: Commands,
+    mut spawn_events: EventReader<SpawnBoss>,
+    bounds: Res<PlayfieldBounds>,
+    scene_assets: Res<SceneAssets>,
+    asset_server: Res<AssetServer>,
+) {
+    if spawn_events.read().last().is_none() {
+        return;
+    }
+
+    let core_size = scene_assets.alien.size() * CORE_SCALE;
+    let start = Vec3::new(bounds.width / 2.0 + core_size.x, 0.0, 0.0);
+    let boss = commands
+        .spawn((
+            Boss {
+                phase: 0,
+                total_health: CORE_HEALTH + 2 * CANNON_HEALTH,
+                age: 0.0,
+                stop_x: bounds.width / 4.0,
+                parked_at: None,
+                cannon_reload: 1.0,
+                core_reload: 1.0,
+                core_volleys: 0,
+            },
+            Position::new(start),
+        ))
+        .id();
+
+    commands.spawn((
+        SpriteBundle {
+            texture: scene_assets.alien.image.clone_weak(),
+            transform: Transform::from_translation(start).with_scale(Vec3::splat(CORE_SCALE)),
+            ..default()
+        },
+        Position::new(start),
+        Alien,
+        BossPart {
+            boss,
+            offset: Vec2::ZERO,
+        },
+        BossCore,
+        Collider::capsule_in(core_size),
+        Health::new(CORE_HEALTH),
+        ScoreValue::new(BOSS_BONUS),
+        CollisionLayers::new(Layer::ENEMY, Layer::PLAYER_SHOT | Layer::HAZARD),
+    ));
+    for side in [1.0, -1.0] {
+        let offset = Vec2::new(CANNON_OFFSET.x, CANNON_OFFSET.y * side);
+        let position = start + offset.extend(0.0);
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.6, 0.7, 1.0),
+                    ..default()
+                },
+                texture: scene_assets.alien.image.clone_weak(),
+                transform: Transform::from_translation(position),
+                ..default()
+            },
+            Position::new(position),
+            Alien,
+            BossPart { boss, offset },
+            BossCannon,
+            Collider::capsule_in(scene_assets.alien.size()),
+            Health::new(CANNON_HEALTH),
+            ScoreValue::new(CANNON_SCORE),
+            CollisionLayers::new(Layer::ENEMY, Layer::PLAYER_SHOT | Layer::HAZARD),
+        ));
+    }
+
+    spawn_health_bar(&mut commands, &asset_server);
+}
+
+fn spawn_health_bar(commands: &mut Commands, asset_server: &AssetServer) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Orbitron-VariableFont_wght.ttf"),
+        font_size: 24.0,
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    bottom: Val::Px(20.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            BossHealthBar,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("MOTHERSHIP", text_style));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(400.0),
+                        height: Val::Px(14.0),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.2, 0.2, 0.2).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: Color::rgb(0.9, 0.2, 0.2).into(),
+                            ..default()
+                        },
+                        BossHealthFill,
+                    ));
+                });
+        });
+}
+
+/// Flies in from the right, then bobs up and down. Parts follow the anchor.
+fn move_boss(
+    time: Res<Time>,
+    mut boss_query: Query<(Entity, &mut Boss, &mut Position), Without<BossPart>>,
+    mut parts_query: Query<(&BossPart, &mut Position), Without<Boss>>,
+) {
+    for (entity, mut boss, mut position) in boss_query.iter_mut() {
+        boss.age += time.delta_seconds();
+        match boss.parked_at {
+            None => {
+                position.value.x -= ENTRY_SPEED * time.delta_seconds();
+                if position.value.x <= boss.stop_x {
+                    position.value.x = boss.stop_x;
+                    boss.parked_at = Some(boss.age);
+                }
+            }
+            Some(parked_at) => {
+                position.value.y = BOB_AMPLITUDE * (BOB_FREQUENCY * (boss.age - parked_at)).sin();
+            }
+        }
+
+        for (part, mut part_position) in parts_query.iter_mut() {
+            if part.boss == entity {
+                part_position.value = position.value + part.offset.extend(0.0);
+            }
+        }
+    }
+}
+
+/// Each phase adds to what the boss fires: aimed spreads from the cannons,
+/// then rings from the core, then a spiral from the core.
+#[allow(clippy::too_many_arguments)]
+fn boss_attacks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut boss_query: Query<(Entity, &mut Boss)>,
+    cannons_query: Query<(Entity, &BossPart, &Position), With<BossCannon>>,
+    core_query: Query<(Entity, &BossPart, &Position), With<BossCore>>,
+    player_query: Query<(&Position, &PlayerVelocity), With<Player>>,
+    scene_assets: Res<SceneAssets>,
+    scene_sounds: Res<SceneSounds>,
+) {
+    let Ok((player_position, player_velocity)) = player_query.get_single() else {
+        return;
+    };
+    for (entity, mut boss) in boss_query.iter_mut() {
+        if boss.parked_at.is_none() {
+            continue;
+        }
+        let mut fired = false;
+
+        boss.cannon_reload -= time.delta_seconds();
+        if boss.cannon_reload <= 0.0 {
+            boss.cannon_reload += if boss.phase == 0 { 1.2 } else { 0.8 };
+            for (cannon, part, position) in cannons_query.iter() {
+                if part.boss != entity {
+                    continue;
+                }
+                fire_pattern(
+                    &mut commands,
+                    &scene_assets,
+                    CANNON_PATTERN,
+                    cannon,
+                    position.value,
+                    player_position.value,
+                    player_velocity.value,
+                    0,
+                );
+                fired = true;
+            }
+        }
+
+        let Some((core, _, core_position)) =
+            core_query.iter().find(|(_, part, _)| part.boss == entity)
+        else {
+            continue;
+        };
+        boss.core_reload -= time.delta_seconds();
+        if boss.phase >= 1 && boss.core_reload <= 0.0 {
+            let pattern = if boss.phase == 1 {
+                boss.core_reload += 2.0;
+                RING_PATTERN
+            } else {
+                boss.core_reload += 0.12;
+                SPIRAL_PATTERN
+            };
+            fire_pattern(
+                &mut commands,
+                &scene_assets,
+                pattern,
+                core,
+                core_position.value,
+                player_position.value,
+                player_velocity.value,
+                boss.core_volleys,
+            );
+            boss.core_volleys += 1;
+            fired = true;
+        }
+
+        if fired {
+            commands.spawn(AudioBundle {
+                source: scene_sounds.alien_fire.clone(),
+                settings: PlaybackSettings {
+                    mode: bevy::audio::PlaybackMode::Remove,
+                    ..default()
+                },
+            });
+        }
+    }
+}
+
+/// Health left across every part, as a fraction of the starting total.
+/// Destroyed parts are gone from `parts` and count as empty.
+fn health_fraction<'a>(
+    boss: (Entity, &Boss),
+    parts: impl Iterator<Item = (&'a BossPart, &'a Health)>,
+) -> f32 {
+    let (entity, boss) = boss;
+    let current: u32 = parts
+        .filter(|(part, _)| part.boss == entity)
+        .map(|(_, health)| health.current)
+        .sum();
+    current as f32 / boss.total_health.max(1) as f32
+}
+
+fn update_phase(
+    mut boss_query: Query<(Entity, &mut Boss)>,
+    parts_query: Query<(&BossPart, &Health)>,
+    mut core_query: Query<(&BossPart, &mut Sprite, Option<&mut HitFlash>), With<BossCore>>,
+) {
+    for (entity, mut boss) in boss_query.iter_mut() {
+        let fraction = health_fraction((entity, &boss), parts_query.iter());
+        let phase = PHASE_THRESHOLDS
+            .iter()
+            .filter(|threshold| fraction <= **threshold)
+            .count();
+        if phase > boss.phase {
+            boss.phase = phase;
+            for (part, mut sprite, flash) in core_query.iter_mut() {
+                if part.boss == entity {
+                    sprite.color = PHASE_COLORS[phase];
+                    // Otherwise a flash still running would end on the old tint.
+                    if let Some(mut flash) = flash {
+                        flash.set_restore(PHASE_COLORS[phase]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn handle_boss_death(
+    mut commands: Commands,
+    mut killed_events: EventReader<Killed>,
+    core_query: Query<&BossPart, With<BossCore>>,
+    parts_query: Query<(Entity, &BossPart), Without<BossCore>>,
+    bar_query: Query<Entity, With<BossHealthBar>>,
+) {
+    for killed in killed_events.read() {
+        let Ok(core) = core_query.get(killed.entity) else {
+            continue;
+        };
+        // The core itself is cleaned up with the other aliens.
+        for (entity, part) in parts_query.iter() {
+            if part.boss == core.boss {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        commands.entity(core.boss).despawn_recursive();
+        for entity in bar_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn update_health_bar(
+    boss_query: Query<(Entity, &Boss)>,
+    parts_query: Query<(&BossPart, &Health)>,
+    mut fill_query: Query<&mut Style, With<BossHealthFill>>,
+) {
+    let Ok(boss) = boss_query.get_single() else {
+        return;
+    };
+    let fraction = health_fraction(boss, parts_query.iter());
+    for mut style in fill_query.iter_mut() {
+        style.width = Val::Percent(fraction * 100.0);
+    }
+}
+
+fn despawn_boss(
+    mut commands: Commands,
+    boss_query: Query<Entity, With<Boss>>,
+    bar_query: Query<Entity, With<BossHealthBar>>,
+) {
+    for entity in boss_query.iter().chain(bar_query.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,196 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    aliens::{spawn_alien_bullet, ALIEN_BULLET_SPEED},
+    movement::Position,
+    scene::SceneAssets,
+    AppState, GameplaySet,
+};
+
+/// Ways for aliens and bosses to fire a volley at the player.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BulletPattern {
+    /// One bullet straight at the target.
+    Aimed,
+    /// One bullet at where a target moving steadily will be when it arrives.
+    Leading,
+    /// `count` bullets fanned out around the target, `angle` radians apart.
+    Fan { count: u32, angle: f32 },
+    /// `count` bullets evenly around a circle, one of them at the target.
+    Ring { count: u32 },
+    /// `arms` bullets evenly around a circle that turns `turn` radians with
+    /// every volley, whatever the target is doing.
+    Spiral { arms: u32, turn: f32 },
+    /// `count` bullets at the target, `interval` seconds apart.
+    Burst { count: u32, interval: f32 },
+}
+
+/// One bullet of a volley.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shot {
+    pub velocity: Vec2,
+    /// Seconds after the volley that the bullet leaves.
+    pub delay: f32,
+}
+
+impl Shot {
+    fn now(direction: Vec2) -> Self {
+        Self {
+            velocity: direction * ALIEN_BULLET_SPEED,
+            delay: 0.0,
+        }
+    }
+}
+
+impl BulletPattern {
+    /// The bullets of one volley fired from `from` at a target at `target`
+    /// moving at `target_velocity`. `volley` counts the shooter's earlier
+    /// volleys.
+    pub fn shots(&self, from: Vec2, target: Vec2, target_velocity: Vec2, volley: u32) -> Vec<Shot> {
+        let aimed = aimed_direction(from, target);
+        match *self {
+            BulletPattern::Aimed => vec![Shot::now(aimed)],
+            BulletPattern::Leading => vec![Shot::now(leading_direction(
+                from,
+                target,
+                target_velocity,
+                ALIEN_BULLET_SPEED,
+            ))],
+            BulletPattern::Fan { count, angle } => (0..count)
+                .map(|i| {
+                    let side = i as f32 - (count - 1) as f32 / 2.0;
+                    Shot::now(Vec2::from_angle(side * angle).rotate(aimed))
+                })
+                .collect(),
+            BulletPattern::Ring { count } => (0..count)
+                .map(|i| Shot::now(Vec2::from_angle(i as f32 * TAU / count as f32).rotate(aimed)))
+                .collect(),
+            BulletPattern::Spiral { arms, turn } => (0..arms)
+                .map(|i| {
+                    let angle = volley as f32 * turn + i as f32 * TAU / arms as f32;
+                    Shot::now(Vec2::from_angle(angle))
+                })
+                .collect(),
+            BulletPattern::Burst { count, interval } => (0..count)
+                .map(|i| Shot {
+                    delay: i as f32 * interval,
+                    ..Shot::now(aimed)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The unit vector from `from` to `to`, or straight left if they are the
+/// same point.
+pub fn aimed_direction(from: Vec2, to: Vec2) -> Vec2 {
+    (to - from).try_normalize().unwrap_or(Vec2::NEG_X)
+}
+
+/// The direction to fire at `speed` to meet a target moving steadily at
+/// `target_velocity`. Falls back to aiming straight at the target when the
+/// bullet could never catch it.
+pub fn leading_direction(from: Vec2, target: Vec2, target_velocity: Vec2, speed: f32) -> Vec2 {
+    // Solve |offset + target_velocity * t| = speed * t for the first t > 0.
+    let offset = target - from;
+    let a = target_velocity.length_squared() - speed * speed;
+    let b = 2.0 * offset.dot(target_velocity);
+    let c = offset.length_squared();
+    let time = if a.abs() < f32::EPSILON {
+        (b.abs() > f32::EPSILON).then(|| -c / b)
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        (discriminant >= 0.0).then(|| {
+            let root = discriminant.sqrt();
+            let (t1, t2) = ((-b - root) / (2.0 * a), (-b + root) / (2.0 * a));
+            if t1 > 0.0 && (t1 < t2 || t2 <= 0.0) {
+                t1
+            } else {
+                t2
+            }
+        })
+    };
+    match time {
+        Some(time) if time > 0.0 => aimed_direction(from, target + target_velocity * time),
+        _ => aimed_direction(from, target),
+    }
+}
+
+/// Counts the volleys an entity has fired, so its spirals keep turning.
+#[derive(Component, Debug, Default)]
+pub struct VolleyCount(pub u32);
+
+/// A bullet waiting to leave its shooter. Dropped if the shooter dies first.
+#[derive(Component, Debug)]
+struct DelayedShot {
+    source: Entity,
+    velocity: Vec2,
+    remaining: f32,
+}
+
+pub struct BulletPatternsPlugin;
+
+impl Plugin for BulletPatternsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            fire_delayed_shots
+                .in_set(GameplaySet::Spawn)
+                .run_if(in_state(AppState::Game)),
+        )
+        .add_systems(OnExit(AppState::Game), despawn_delayed_shots);
+    }
+}
+
+/// Fires one volley of `pattern` from `source`, which is at `from`. Delayed
+/// bullets leave from wherever `source` has got to by then.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn fire_pattern(
+    commands: &mut Commands,
+    scene_assets: &SceneAssets,
+    pattern: BulletPattern,
+    source: Entity,
+    from: Vec3,
+    target: Vec3,
+    target_velocity: Vec2,
+    volley: u32,
+) {
+    for shot in pattern.shots(from.truncate(), target.truncate(), target_velocity, volley) {
+        if shot.delay > 0.0 {
+            commands.spawn(DelayedShot {
+                source,
+                velocity: shot.velocity,
+                remaining: shot.delay,
+            });
+        } else {
+            spawn_alien_bullet(commands, scene_assets, from, shot.velocity);
+        }
+    }
+}
+
+fn fire_delayed_shots(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut DelayedShot)>,
+    source_query: Query<&Position>,
+    scene_assets: Res<SceneAssets>,
+) {
+    for (entity, mut shot) in query.iter_mut() {
+        shot.remaining -= time.delta_seconds();
+        if shot.remaining > 0.0 {
+            continue;
+        }
+        if let Ok(position) = source_query.get(shot.source) {
+            spawn_alien_bullet(&mut commands, &scene_assets, position.value, shot.velocity);
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+fn despawn_delayed_shots(mut commands: Commands, query: Query<Entity, With<DelayedShot>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
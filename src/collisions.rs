@@ -1,68 +1,505 @@
-use bevy::{prelude::*, utils::HashMap};
+use bevy::{prelude::*, render::render_resource::TextureFormat, utils::HashSet};
 
 use crate::{
-    fighter::{IsBullet, Team},
-    scene::Size,
-    AppState,
+    fighter::Invulnerable, movement::Position, replay::ReplayPlayback, spatial_hash::SpatialHash,
+    AppState, GameplaySet,
 };
 
-#[derive(Component, Debug)]
+/// Roughly the size of a ship, so most colliders touch one to four cells.
+const CELL_SIZE: f32 = 64.0;
+
+/// The solid part of an entity, centred on its position plus `offset`.
+#[derive(Component, Debug, Clone)]
 pub struct Collider {
-    pub size: Size,
-    pub colliding_entities: Vec<Entity>,
+    pub shape: ColliderShape,
+    pub offset: Vec2,
+    /// Refines the shape to the sprite's opaque pixels while
+    /// [`PixelPerfectCollisions`] is on.
+    pub alpha_mask: Option<Handle<Image>>,
+}
+
+/// Turns on the alpha mask refinement for colliders that have one.
+///
+/// Off by default. Headless runs have no images to sample, so replays are
+/// always played back with it off, and a round played with it on may not
+/// replay exactly.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PixelPerfectCollisions(pub bool);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColliderShape {
+    Aabb {
+        half_size: Vec2,
+    },
+    Circle {
+        radius: f32,
+    },
+    /// A horizontal segment of `2 * half_length` swept by `radius`, which
+    /// suits ships and bullets that always face along the x axis.
+    Capsule {
+        half_length: f32,
+        radius: f32,
+    },
 }
 
 impl Collider {
-    pub fn new(size: Size) -> Self {
+    fn from_shape(shape: ColliderShape) -> Self {
         Self {
-            size,
-            colliding_entities: vec![],
+            shape,
+            offset: Vec2::ZERO,
+            alpha_mask: None,
+        }
+    }
+
+    pub fn aabb(half_extents: Vec2) -> Self {
+        Self::from_shape(ColliderShape::Aabb {
+            half_size: half_extents,
+        })
+    }
+
+    pub fn circle(radius: f32) -> Self {
+        Self::from_shape(ColliderShape::Circle { radius })
+    }
+
+    pub fn capsule(half_length: f32, radius: f32) -> Self {
+        Self::from_shape(ColliderShape::Capsule {
+            half_length,
+            radius,
+        })
+    }
+
+    /// The largest horizontal capsule that fits in a box of `size`.
+    pub fn capsule_in(size: Vec2) -> Self {
+        let radius = size.y / 2.0;
+        Self::capsule((size.x / 2.0 - radius).max(0.0), radius)
+    }
+
+    pub fn with_offset(mut self, offset: Vec2) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_alpha_mask(mut self, image: Handle<Image>) -> Self {
+        self.alpha_mask = Some(image);
+        self
+    }
+
+    /// Whether the shapes overlap for entities at `position` and
+    /// `other_position`, ignoring any alpha mask.
+    pub fn overlaps(&self, position: Vec2, other: &Collider, other_position: Vec2) -> bool {
+        self.shape.overlaps(
+            position + self.offset,
+            &other.shape,
+            other_position + other.offset,
+        )
+    }
+}
+
+impl ColliderShape {
+    /// Full width and height of the box around the shape.
+    pub fn bounding_size(&self) -> Vec2 {
+        match *self {
+            ColliderShape::Aabb { half_size } => half_size * 2.0,
+            ColliderShape::Circle { radius } => Vec2::splat(radius * 2.0),
+            ColliderShape::Capsule {
+                half_length,
+                radius,
+            } => Vec2::new(half_length + radius, radius) * 2.0,
+        }
+    }
+
+    /// Whether `point`, relative to the shape's centre, is inside it.
+    pub fn contains(&self, point: Vec2) -> bool {
+        match self.rounded() {
+            Ok((half_length, radius)) => {
+                let dx = (point.x.abs() - half_length).max(0.0);
+                dx * dx + point.y * point.y < radius * radius
+            }
+            Err(half_size) => point.x.abs() < half_size.x && point.y.abs() < half_size.y,
         }
     }
+
+    /// Whether this shape at `center` overlaps `other` at `other_center`.
+    pub fn overlaps(&self, center: Vec2, other: &ColliderShape, other_center: Vec2) -> bool {
+        let distance = (center - other_center).abs();
+        match (self.rounded(), other.rounded()) {
+            (Err(half_a), Err(half_b)) => {
+                distance.x < half_a.x + half_b.x && distance.y < half_a.y + half_b.y
+            }
+            (Ok((length_a, radius_a)), Ok((length_b, radius_b))) => {
+                let dx = (distance.x - length_a - length_b).max(0.0);
+                let reach = radius_a + radius_b;
+                dx * dx + distance.y * distance.y < reach * reach
+            }
+            (Ok((half_length, radius)), Err(half_size))
+            | (Err(half_size), Ok((half_length, radius))) => {
+                let dx = (distance.x - half_length - half_size.x).max(0.0);
+                let dy = (distance.y - half_size.y).max(0.0);
+                dx * dx + dy * dy < radius * radius
+            }
+        }
+    }
+
+    /// Circles and capsules as `(half_length, radius)`, boxes as their half size.
+    fn rounded(&self) -> Result<(f32, f32), Vec2> {
+        match *self {
+            ColliderShape::Aabb { half_size } => Err(half_size),
+            ColliderShape::Circle { radius } => Ok((0.0, radius)),
+            ColliderShape::Capsule {
+                half_length,
+                radius,
+            } => Ok((half_length, radius)),
+        }
+    }
+}
+
+/// Named bits for [`CollisionLayers`].
+pub struct Layer;
+
+impl Layer {
+    pub const PLAYER: u32 = 1 << 0;
+    pub const PLAYER_SHOT: u32 = 1 << 1;
+    pub const ENEMY: u32 = 1 << 2;
+    pub const ENEMY_SHOT: u32 = 1 << 3;
+    pub const PICKUP: u32 = 1 << 4;
+    /// Hits everything that lists it in its filter.
+    pub const HAZARD: u32 = 1 << 5;
+    pub const ALL: u32 = u32::MAX;
+}
+
+/// What a collider is (`member`) and what it can hit (`filter`).
+///
+/// Two colliders only interact when each one's filter contains a layer the
+/// other is a member of.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionLayers {
+    pub member: u32,
+    pub filter: u32,
+}
+
+impl CollisionLayers {
+    pub fn new(member: u32, filter: u32) -> Self {
+        Self { member, filter }
+    }
+
+    pub fn interacts_with(&self, other: &CollisionLayers) -> bool {
+        self.filter & other.member != 0 && other.filter & self.member != 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionPhase {
+    /// The pair started touching this tick.
+    Enter,
+    /// The pair was already touching last tick.
+    Stay,
+    /// The pair stopped touching, or one of them was despawned.
+    Exit,
+}
+
+/// Sent once per tick for each pair of colliders that can hit each other
+/// and are touching, or just stopped touching. `a` and `b` are in no
+/// particular order.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionEvent {
+    pub a: Entity,
+    pub b: Entity,
+    pub phase: CollisionPhase,
+}
+
+impl CollisionEvent {
+    /// Both ways round, as `(entity, other)`, so a handler can look for its
+    /// own entities on either side.
+    pub fn pairs(&self) -> [(Entity, Entity); 2] {
+        [(self.a, self.b), (self.b, self.a)]
+    }
 }
 
+/// Pairs that were touching at the end of the last tick, for telling
+/// `Enter` from `Stay`. Cleared when a round starts so nothing carries over.
+#[derive(Resource, Debug, Default)]
+struct TouchingPairs(Vec<(Entity, Entity)>);
+
 pub struct CollisionDetectionPlugin;
 
 impl Plugin for CollisionDetectionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, collision_detection.run_if(in_state(AppState::Game)));
+        app.add_event::<CollisionEvent>()
+            .init_resource::<PixelPerfectCollisions>()
+            .init_resource::<TouchingPairs>()
+            .add_systems(OnEnter(AppState::Game), reset_touching_pairs)
+            .add_systems(
+                FixedUpdate,
+                collision_detection
+                    .in_set(GameplaySet::CollisionDetection)
+                    .run_if(in_state(AppState::Game)),
+            );
     }
 }
 
-fn collision_detection(
-    mut query: Query<(Entity, &GlobalTransform, &mut Collider, &Team, &IsBullet)>,
-) {
-    let mut colliding_entities: HashMap<Entity, Vec<Entity>> = HashMap::new();
-
-    for (entiity_a, transform_a, collider_a, team_a, is_bullet_a) in query.iter() {
-        for (entity_b, transform_b, collider_b, team_b, is_bullet_b) in query.iter() {
-            if entiity_a != entity_b
-                && team_a.value != team_b.value
-                && is_bullet_a.value != is_bullet_b.value
-            {
-                let distance = (transform_a.translation() - transform_b.translation()).abs();
-
-                // let distance = transform_a
-                //     .translation()
-                //     .distance(transform_b.translation());
-                if distance.x < collider_a.size.value.x / 2.0 + collider_b.size.value.x / 2.0
-                    && distance.y < collider_a.size.value.y / 2.0 + collider_b.size.value.y / 2.0
-                {
-                    colliding_entities
-                        .entry(entiity_a)
-                        .or_insert_with(Vec::new)
-                        .push(entity_b);
+fn reset_touching_pairs(mut touching: ResMut<TouchingPairs>) {
+    touching.0.clear();
+}
+
+/// A collider placed in the world for one tick.
+struct Body<'a> {
+    entity: Entity,
+    layers: CollisionLayers,
+    collider: &'a Collider,
+    /// Where the sprite is drawn, which the alpha mask is aligned to.
+    position: Vec2,
+}
+
+impl Body<'_> {
+    fn center(&self) -> Vec2 {
+        self.position + self.collider.offset
+    }
+
+    /// `images` is only given when the alpha masks should be used.
+    fn overlaps(&self, other: &Body, images: Option<&Assets<Image>>) -> bool {
+        if !self
+            .collider
+            .overlaps(self.position, other.collider, other.position)
+        {
+            return false;
+        }
+        let Some(images) = images else {
+            return true;
+        };
+        if self.collider.alpha_mask.is_none() && other.collider.alpha_mask.is_none() {
+            return true;
+        }
+
+        // Sample the shared part of both bounding boxes a pixel at a time.
+        let half_a = self.collider.shape.bounding_size() / 2.0;
+        let half_b = other.collider.shape.bounding_size() / 2.0;
+        let min = (self.center() - half_a)
+            .max(other.center() - half_b)
+            .floor();
+        let max = (self.center() + half_a).min(other.center() + half_b).ceil();
+        let mut y = min.y + 0.5;
+        while y < max.y {
+            let mut x = min.x + 0.5;
+            while x < max.x {
+                let point = Vec2::new(x, y);
+                if self.is_solid_at(point, images) && other.is_solid_at(point, images) {
+                    return true;
                 }
+                x += 1.0;
             }
+            y += 1.0;
         }
+        false
+    }
+
+    fn is_solid_at(&self, point: Vec2, images: &Assets<Image>) -> bool {
+        if !self.collider.shape.contains(point - self.center()) {
+            return false;
+        }
+        // Without a loaded RGBA image to sample, the shape alone decides.
+        let Some(image) = self
+            .collider
+            .alpha_mask
+            .as_ref()
+            .and_then(|handle| images.get(handle))
+        else {
+            return true;
+        };
+        if !matches!(
+            image.texture_descriptor.format,
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb
+        ) {
+            return true;
+        }
+
+        let size = image.size();
+        let pixel = Vec2::new(
+            point.x - self.position.x + size.x as f32 / 2.0,
+            size.y as f32 / 2.0 - (point.y - self.position.y),
+        );
+        if pixel.x < 0.0 || pixel.y < 0.0 {
+            return false;
+        }
+        let (x, y) = (pixel.x as u32, pixel.y as u32);
+        if x >= size.x || y >= size.y {
+            return false;
+        }
+        let alpha = (y * size.x + x) as usize * 4 + 3;
+        image.data.get(alpha).is_some_and(|&alpha| alpha > 0)
+    }
+}
+
+fn collision_detection(
+    query: Query<(
+        Entity,
+        &Position,
+        &Collider,
+        &CollisionLayers,
+        Has<Invulnerable>,
+    )>,
+    pixel_perfect: Res<PixelPerfectCollisions>,
+    playback: Option<Res<ReplayPlayback>>,
+    images: Res<Assets<Image>>,
+    mut grid: Local<Option<SpatialHash>>,
+    mut touching: ResMut<TouchingPairs>,
+    mut collision_events: EventWriter<CollisionEvent>,
+) {
+    let grid = grid.get_or_insert_with(|| SpatialHash::new(CELL_SIZE));
+    grid.clear();
+    let images = (pixel_perfect.0 && playback.is_none()).then_some(&*images);
+
+    let mut bodies = Vec::new();
+    for (entity, position, collider, &layers, invulnerable) in query.iter() {
+        // Nothing can hurt an invulnerable ship, but it still collects pickups.
+        let layers = if invulnerable {
+            CollisionLayers::new(layers.member, layers.filter & Layer::PICKUP)
+        } else {
+            layers
+        };
+        let body = Body {
+            entity,
+            layers,
+            collider,
+            position: position.value.truncate(),
+        };
+        grid.insert(body.center(), collider.shape.bounding_size());
+        bodies.push(body);
+    }
+
+    let mut now_touching = Vec::new();
+    grid.for_each_overlapping_pair(|a, b| {
+        let (a, b) = (&bodies[a], &bodies[b]);
+        if a.layers.interacts_with(&b.layers) && a.overlaps(b, images) {
+            now_touching.push((a.entity, b.entity));
+        }
+    });
+
+    // Pairs are compared in both orders, since query order can change
+    // between ticks as entities come and go.
+    let was_touching: HashSet<(Entity, Entity)> = touching.0.iter().copied().collect();
+    let still_touching: HashSet<(Entity, Entity)> = now_touching
+        .iter()
+        .flat_map(|&(a, b)| [(a, b), (b, a)])
+        .collect();
+
+    for &(a, b) in touching.0.iter() {
+        if !still_touching.contains(&(a, b)) {
+            collision_events.send(CollisionEvent {
+                a,
+                b,
+                phase: CollisionPhase::Exit,
+            });
+        }
+    }
+    for &(a, b) in now_touching.iter() {
+        let phase = if was_touching.contains(&(a, b)) || was_touching.contains(&(b, a)) {
+            CollisionPhase::Stay
+        } else {
+            CollisionPhase::Enter
+        };
+        collision_events.send(CollisionEvent { a, b, phase });
+    }
+
+    touching.0 = now_touching;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOX: ColliderShape = ColliderShape::Aabb {
+        half_size: Vec2::new(10.0, 5.0),
+    };
+    const CIRCLE: ColliderShape = ColliderShape::Circle { radius: 5.0 };
+    const CAPSULE: ColliderShape = ColliderShape::Capsule {
+        half_length: 10.0,
+        radius: 5.0,
+    };
+
+    /// Checks both argument orders, since `overlaps` should not care.
+    fn overlaps(a: ColliderShape, a_center: Vec2, b: ColliderShape, b_center: Vec2) -> bool {
+        let forward = a.overlaps(a_center, &b, b_center);
+        assert_eq!(forward, b.overlaps(b_center, &a, a_center));
+        forward
+    }
+
+    #[test]
+    fn boxes_overlap_on_both_axes() {
+        assert!(overlaps(BOX, Vec2::ZERO, BOX, Vec2::new(19.0, 9.0)));
+        assert!(!overlaps(BOX, Vec2::ZERO, BOX, Vec2::new(21.0, 0.0)));
+        assert!(!overlaps(BOX, Vec2::ZERO, BOX, Vec2::new(0.0, 11.0)));
+        // Touching edges don't count.
+        assert!(!overlaps(BOX, Vec2::ZERO, BOX, Vec2::new(20.0, 0.0)));
+    }
+
+    #[test]
+    fn circles_overlap_within_their_radii() {
+        assert!(overlaps(CIRCLE, Vec2::ZERO, CIRCLE, Vec2::new(6.0, 6.0)));
+        // Inside the bounding boxes, but not the circles.
+        assert!(!overlaps(CIRCLE, Vec2::ZERO, CIRCLE, Vec2::new(8.0, 8.0)));
+        assert!(!overlaps(CIRCLE, Vec2::ZERO, CIRCLE, Vec2::new(10.0, 0.0)));
+    }
+
+    #[test]
+    fn capsules_overlap_along_their_length() {
+        assert!(overlaps(CAPSULE, Vec2::ZERO, CAPSULE, Vec2::new(29.0, 0.0)));
+        assert!(overlaps(CAPSULE, Vec2::ZERO, CAPSULE, Vec2::new(15.0, 9.0)));
+        assert!(!overlaps(
+            CAPSULE,
+            Vec2::ZERO,
+            CAPSULE,
+            Vec2::new(31.0, 0.0)
+        ));
+        // Past the rounded ends, though inside the bounding boxes.
+        assert!(!overlaps(
+            CAPSULE,
+            Vec2::ZERO,
+            CAPSULE,
+            Vec2::new(28.0, 8.0)
+        ));
+    }
+
+    #[test]
+    fn circle_and_capsule() {
+        assert!(overlaps(CIRCLE, Vec2::new(8.0, 8.0), CAPSULE, Vec2::ZERO));
+        assert!(overlaps(CIRCLE, Vec2::new(19.0, 0.0), CAPSULE, Vec2::ZERO));
+        assert!(!overlaps(CIRCLE, Vec2::new(21.0, 0.0), CAPSULE, Vec2::ZERO));
+        assert!(!overlaps(CIRCLE, Vec2::new(17.0, 8.0), CAPSULE, Vec2::ZERO));
+    }
+
+    #[test]
+    fn box_and_circle() {
+        assert!(overlaps(BOX, Vec2::ZERO, CIRCLE, Vec2::new(14.0, 0.0)));
+        assert!(overlaps(BOX, Vec2::ZERO, CIRCLE, Vec2::new(0.0, 9.0)));
+        assert!(!overlaps(BOX, Vec2::ZERO, CIRCLE, Vec2::new(0.0, 11.0)));
+        // Off the corner, where the circle's bounding box would still hit.
+        assert!(!overlaps(BOX, Vec2::ZERO, CIRCLE, Vec2::new(14.0, 9.0)));
+    }
+
+    #[test]
+    fn box_and_capsule() {
+        assert!(overlaps(BOX, Vec2::ZERO, CAPSULE, Vec2::new(24.0, 0.0)));
+        assert!(overlaps(BOX, Vec2::ZERO, CAPSULE, Vec2::new(0.0, 9.0)));
+        assert!(!overlaps(BOX, Vec2::ZERO, CAPSULE, Vec2::new(26.0, 0.0)));
+        assert!(!overlaps(BOX, Vec2::ZERO, CAPSULE, Vec2::new(24.0, 9.0)));
     }
 
-    for (entity, _, mut collider, _, _) in query.iter_mut() {
-        collider.colliding_entities.clear();
-        if let Some(collisions) = colliding_entities.get(&entity) {
-            collider
-                .colliding_entities
-                .extend(collisions.iter().copied());
+    #[test]
+    fn offsets_move_every_shape() {
+        let offset = Vec2::new(30.0, -20.0);
+        let target = Collider::aabb(Vec2::splat(2.0));
+        for collider in [
+            Collider::aabb(Vec2::new(10.0, 5.0)),
+            Collider::circle(5.0),
+            Collider::capsule(10.0, 5.0),
+        ] {
+            let shifted = collider.clone().with_offset(offset);
+            assert!(collider.overlaps(Vec2::ZERO, &target, Vec2::ZERO));
+            assert!(!shifted.overlaps(Vec2::ZERO, &target, Vec2::ZERO));
+            assert!(shifted.overlaps(Vec2::ZERO, &target, offset));
+            assert!(target.overlaps(offset, &shifted, Vec2::ZERO));
+            // Offsets on both sides cancel out.
+            assert!(shifted.overlaps(Vec2::ZERO, &target.clone().with_offset(offset), Vec2::ZERO));
         }
     }
 }
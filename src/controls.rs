@@ -0,0 +1,327 @@
+use bevy::prelude::*;
+
+use crate::{
+    actions::{key_label, Action, ActionState, KeyBindings},
+    mouse::SteeringMode,
+    storage::Storage,
+    AppState,
+};
+
+const STORAGE_KEY: &str = "controls";
+const STEERING_STORAGE_KEY: &str = "steering";
+
+const IDLE_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.0);
+const SELECTED_COLOR: Color = Color::rgba(0.35, 0.75, 0.35, 0.6);
+const LISTENING_COLOR: Color = Color::rgba(0.85, 0.6, 0.2, 0.6);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum ControlsEntry {
+    Rebind(Action),
+    Steering,
+    ResetToDefaults,
+    Done,
+}
+
+impl ControlsEntry {
+    const COUNT: usize = Action::ALL.len() + 3;
+
+    fn nth(index: usize) -> Self {
+        match Action::ALL.get(index) {
+            Some(action) => ControlsEntry::Rebind(*action),
+            None => match index - Action::ALL.len() {
+                0 => ControlsEntry::Steering,
+                1 => ControlsEntry::ResetToDefaults,
+                _ => ControlsEntry::Done,
+            },
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ControlsEntry::Rebind(action) => action.label(),
+            ControlsEntry::Steering => "STEERING",
+            ControlsEntry::ResetToDefaults => "RESET TO DEFAULTS",
+            ControlsEntry::Done => "DONE",
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct ControlsMenu {
+    /// Index of the highlighted entry.
+    selected: usize,
+    /// Set while waiting for the key to bind to this action.
+    listening: Option<Action>,
+    message: String,
+}
+
+#[derive(Component, Debug)]
+struct ControlsScreen;
+
+/// The keys shown next to an entry.
+#[derive(Component, Debug)]
+struct BindingText(ControlsEntry);
+
+#[derive(Component, Debug)]
+struct MessageText;
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ControlsMenu>()
+            .add_systems(Startup, load_controls)
+            .add_systems(OnEnter(AppState::Controls), setup)
+            .add_systems(
+                Update,
+                (navigate_menu, refresh_menu)
+                    .chain()
+                    .run_if(in_state(AppState::Controls)),
+            )
+            .add_systems(OnExit(AppState::Controls), despawn);
+    }
+}
+
+/// Actions missing from the saved file, like ones added since it was
+/// written, keep their default keys.
+fn load_controls(
+    storage: Res<Storage>,
+    mut bindings: ResMut<KeyBindings>,
+    mut steering: ResMut<SteeringMode>,
+) {
+    if let Some(mut stored) = storage.load::<KeyBindings>(STORAGE_KEY) {
+        for (action, keys) in KeyBindings::default().keys {
+            stored.keys.entry(action).or_insert(keys);
+        }
+        *bindings = stored;
+    }
+    if let Some(stored) = storage.load::<SteeringMode>(STEERING_STORAGE_KEY) {
+        *steering = stored;
+    }
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, mut menu: ResMut<ControlsMenu>) {
+    *menu = ControlsMenu::default();
+    let font = asset_server.load("fonts/Orbitron-VariableFont_wght.ttf");
+    let row_style = TextStyle {
+        font: font.clone(),
+        font_size: 24.0,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            ControlsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    "CONTROLS",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 50.0,
+                        ..default()
+                    },
+                ),
+                style: Style {
+                    margin: UiRect::bottom(Val::Px(10.0)),
+                    ..default()
+                },
+                ..default()
+            });
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    "KEYBOARD KEYS ONLY. GAMEPAD BUTTONS ARE FIXED.",
+                    row_style.clone(),
+                ),
+                style: Style {
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    ..default()
+                },
+                ..default()
+            });
+
+            for index in 0..ControlsEntry::COUNT {
+                let entry = ControlsEntry::nth(index);
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(640.0),
+                                padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                                justify_content: JustifyContent::SpaceBetween,
+                                ..default()
+                            },
+                            background_color: IDLE_COLOR.into(),
+                            ..default()
+                        },
+                        entry,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(entry.label(), row_style.clone()));
+                        parent.spawn((
+                            TextBundle::from_section("", row_style.clone()),
+                            BindingText(entry),
+                        ));
+                    });
+            }
+
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section("", row_style),
+                    style: Style {
+                        margin: UiRect::top(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                MessageText,
+            ));
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn navigate_menu(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
+    buttons: Query<(&Interaction, &ControlsEntry), Changed<Interaction>>,
+    mut menu: ResMut<ControlsMenu>,
+    mut bindings: ResMut<KeyBindings>,
+    mut steering: ResMut<SteeringMode>,
+    mut storage: ResMut<Storage>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    // The next key pressed is the new binding, unless it is Back, which
+    // leaves the old binding alone. A gamepad's Back button cancels too.
+    if let Some(action) = menu.listening {
+        if actions.just_pressed(Action::Back) {
+            menu.listening = None;
+            menu.message = String::new();
+            return;
+        }
+        let Some(key) = keyboard_input.get_just_pressed().next().copied() else {
+            return;
+        };
+        menu.listening = None;
+        menu.message = match bindings.clash(action, key) {
+            Some(other) => format!("{} IS ALREADY USED FOR {}", key_label(key), other.label()),
+            None => {
+                bindings.keys.insert(action, vec![key]);
+                storage.save(STORAGE_KEY, &*bindings);
+                String::new()
+            }
+        };
+        return;
+    }
+
+    if actions.just_pressed(Action::MoveUp) {
+        menu.selected = (menu.selected + ControlsEntry::COUNT - 1) % ControlsEntry::COUNT;
+    }
+    if actions.just_pressed(Action::MoveDown) {
+        menu.selected = (menu.selected + 1) % ControlsEntry::COUNT;
+    }
+
+    let mut chosen = actions
+        .just_pressed(Action::Confirm)
+        .then(|| ControlsEntry::nth(menu.selected));
+    for (interaction, entry) in buttons.iter() {
+        let index = (0..ControlsEntry::COUNT)
+            .position(|index| ControlsEntry::nth(index) == *entry)
+            .unwrap();
+        match interaction {
+            Interaction::Hovered => menu.selected = index,
+            Interaction::Pressed => chosen = Some(*entry),
+            Interaction::None => {}
+        }
+    }
+    if actions.just_pressed(Action::Back) {
+        chosen = Some(ControlsEntry::Done);
+    }
+
+    match chosen {
+        Some(ControlsEntry::Rebind(action)) => {
+            menu.listening = Some(action);
+            let cancel = bindings
+                .keys
+                .get(&Action::Back)
+                .and_then(|keys| keys.first())
+                .map_or_else(|| Action::Back.label().to_string(), |key| key_label(*key));
+            menu.message = format!("PRESS A KEY FOR {} ( {cancel} TO CANCEL )", action.label());
+        }
+        Some(ControlsEntry::Steering) => {
+            *steering = steering.toggled();
+            storage.save(STEERING_STORAGE_KEY, &*steering);
+        }
+        Some(ControlsEntry::ResetToDefaults) => {
+            *bindings = KeyBindings::default();
+            storage.save(STORAGE_KEY, &*bindings);
+            *steering = SteeringMode::default();
+            storage.save(STEERING_STORAGE_KEY, &*steering);
+            menu.message = "CONTROLS RESET".to_string();
+        }
+        Some(ControlsEntry::Done) => next_state.set(AppState::IntroScreen),
+        None => {}
+    }
+}
+
+fn refresh_menu(
+    menu: Res<ControlsMenu>,
+    bindings: Res<KeyBindings>,
+    steering: Res<SteeringMode>,
+    mut buttons: Query<(&ControlsEntry, &mut BackgroundColor)>,
+    mut binding_texts: Query<(&BindingText, &mut Text), Without<MessageText>>,
+    mut message_texts: Query<&mut Text, With<MessageText>>,
+) {
+    if !menu.is_changed() && !bindings.is_changed() && !steering.is_changed() {
+        return;
+    }
+
+    let selected = ControlsEntry::nth(menu.selected);
+    for (entry, mut background) in buttons.iter_mut() {
+        *background = match (*entry == selected, menu.listening) {
+            (true, Some(_)) => LISTENING_COLOR,
+            (true, None) => SELECTED_COLOR,
+            (false, _) => IDLE_COLOR,
+        }
+        .into();
+    }
+
+    for (BindingText(entry), mut text) in binding_texts.iter_mut() {
+        text.sections[0].value = match entry {
+            ControlsEntry::Rebind(action) if menu.listening == Some(*action) => "...".to_string(),
+            ControlsEntry::Rebind(action) => bindings
+                .keys
+                .get(action)
+                .map(|keys| {
+                    keys.iter()
+                        .map(|key| key_label(*key))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default(),
+            ControlsEntry::Steering => steering.label().to_string(),
+            _ => String::new(),
+        };
+    }
+
+    for mut text in message_texts.iter_mut() {
+        text.sections[0].value.clone_from(&menu.message);
+    }
+}
+
+fn despawn(mut commands: Commands, query: Query<Entity, With<ControlsScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,16 +1,23 @@
-use bevy::{prelude::*, transform::commands};
+use bevy::prelude::*;
+use rand::Rng;
 
 use crate::{
     movement::{Position, Velocity},
-    AppState,
+    scene::{SceneAssets, Size},
+    AppState, GameplaySet,
 };
 
 pub struct ExplosionsPlugin;
 
 impl Plugin for ExplosionsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, handle_explosions.run_if(in_state(AppState::Game)))
-            .add_systems(OnExit(AppState::Game), despawn_explosions);
+        app.add_systems(
+            FixedUpdate,
+            handle_explosions
+                .in_set(GameplaySet::Movement)
+                .run_if(in_state(AppState::Game)),
+        )
+        .add_systems(OnExit(AppState::Game), despawn_explosions);
     }
 }
 
@@ -27,35 +34,18 @@ pub struct Explosion {
 }
 
 impl Explosion {
-    pub fn new(x: f32, y: f32) -> Self {
-        let x = x + rand::random::<f32>() * 32. - rand::random::<f32>() * 32.;
-        let y = y + rand::random::<f32>() * 32. as f32 - rand::random::<f32>() * 32.;
-        let dx = (rand::random::<f32>()) - (rand::random::<f32>());
-        let dy = (rand::random::<f32>()) - (rand::random::<f32>());
-        let mut r: f32 = 0.;
-        let mut g: f32 = 0.;
-        let mut b: f32 = 0.;
+    pub fn new(x: f32, y: f32, rng: &mut impl Rng) -> Self {
+        let x = x + rng.gen::<f32>() * 32. - rng.gen::<f32>() * 32.;
+        let y = y + rng.gen::<f32>() * 32. - rng.gen::<f32>() * 32.;
+        let dx = rng.gen::<f32>() - rng.gen::<f32>();
+        let dy = rng.gen::<f32>() - rng.gen::<f32>();
 
-        let seed = rand::random::<u8>() % 4;
-
-        match seed {
-            0 => {
-                r = 1.0;
-            }
-            1 => {
-                r = 1.0;
-                g = 1.0;
-            }
-            2 => {
-                r = 1.0;
-                g = 0.5;
-            }
-            _ => {
-                r = 1.0;
-                g = 1.0;
-                b = 1.0;
-            }
-        }
+        let (r, g, b) = match rng.gen_range(0..4) {
+            0 => (1.0, 0.0, 0.0),
+            1 => (1.0, 1.0, 0.0),
+            2 => (1.0, 0.5, 0.0),
+            _ => (1.0, 1.0, 1.0),
+        };
 
         Self {
             x,
@@ -65,28 +55,64 @@ impl Explosion {
             r,
             g,
             b,
-            a: rand::random::<f32>(),
+            a: rng.gen::<f32>(),
         }
     }
+
+    /// A fast spark thrown straight out from `(x, y)` at `angle`, for the
+    /// ring of a shockwave.
+    pub fn shockwave(x: f32, y: f32, angle: f32, rng: &mut impl Rng) -> Self {
+        let (dy, dx) = angle.sin_cos();
+        let speed = rng.gen_range(10.0..14.0);
+        Self {
+            x,
+            y,
+            dx: dx * speed,
+            dy: dy * speed,
+            r: 0.6,
+            g: 0.9,
+            b: 1.0,
+            a: 1.0,
+        }
+    }
+}
+
+/// Spawns one particle of an explosion.
+pub(crate) fn spawn_explosion(
+    commands: &mut Commands,
+    scene_assets: &SceneAssets,
+    explosion: Explosion,
+) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::Rgba {
+                    red: explosion.r,
+                    green: explosion.g,
+                    blue: explosion.b,
+                    alpha: explosion.a,
+                },
+                ..default()
+            },
+            texture: scene_assets.explosion.image.clone(),
+            transform: Transform::from_xyz(explosion.x, explosion.y, 0.0),
+            ..default()
+        },
+        Velocity::new(Vec3::new(explosion.dx, explosion.dy, 0.0)),
+        Position::new(Vec3::new(explosion.x, explosion.y, 0.0)),
+        Size::new(scene_assets.explosion.dimensions),
+        explosion,
+    ));
 }
 
 fn handle_explosions(
     time: Res<Time>,
     mut commands: Commands,
-    mut query: Query<
-        (
-            &Velocity,
-            &mut Position,
-            &mut Transform,
-            &mut Sprite,
-            Entity,
-        ),
-        With<Explosion>,
-    >,
+    mut query: Query<(&Velocity, &mut Position, &mut Sprite, Entity), With<Explosion>>,
 ) {
-    for (velocity, mut position, mut tranform, mut sprite, entity) in query.iter_mut() {
-        position.value += velocity.value;
-        tranform.translation += velocity.value;
+    for (velocity, mut position, mut sprite, entity) in query.iter_mut() {
+        // Explosion velocities are in pixels per 60 Hz frame.
+        position.value += velocity.value * 60.0 * time.delta_seconds();
         let new_a = sprite.color.a() - (time.delta_seconds());
         if new_a < 0.0 {
             commands.entity(entity).despawn_recursive();
@@ -1,17 +1,28 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
 
 use crate::{
-    collisions::Collider,
-    explosions::Explosion,
+    actions::{Action, ActionState},
+    aliens::{Alien, AlienBullet},
+    collisions::{Collider, CollisionEvent, CollisionLayers, CollisionPhase, Layer},
+    explosions::{spawn_explosion, Explosion},
+    health::{Damage, Health, Killed},
     hud::CurrentScore,
     movement::{Position, Velocity},
-    scene::{SceneAssets, SceneSounds, Size},
-    AppState,
+    pickups::PowerUps,
+    replay::ReplayPlayback,
+    rng::GameRng,
+    scene::{PlayfieldBounds, SceneAssets, SceneSounds, Size},
+    weapons::Weapon,
+    AppState, GameplaySet,
 };
 
 const PLAYER_SPEED: f32 = 250.0;
-const PLAYER_RELOAD: f32 = 12.0;
-const PLAYER_BULLET_SPEED: f32 = 500.0;
+/// Only the cockpit counts as a hit, so bullets can graze the wings.
+const PLAYER_HITBOX_RADIUS: f32 = 6.0;
+const PLAYER_HEALTH: u32 = 1;
+const RESPAWN_DELAY: f32 = 1.0;
+const RESPAWN_INVULNERABILITY: f32 = 2.0;
+const BLINKS_PER_SECOND: f32 = 10.0;
 
 #[derive(Component, Debug)]
 pub struct Reload {
@@ -30,27 +41,62 @@ pub struct Player;
 #[derive(Component, Debug)]
 pub struct PlayerBullet;
 
+/// How fast the player moved on the last tick, for aliens that lead their
+/// shots.
+#[derive(Component, Debug, Default)]
+pub struct PlayerVelocity {
+    pub value: Vec2,
+}
+
 pub struct FighterPlugin;
 
-#[derive(Component, Debug)]
-pub struct Team {
-    pub value: u8,
+/// The player's controls for the current tick.
+///
+/// Filled from the `ActionState`, or from a replay while one is playing back.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerInput {
+    /// Steering from -127 (left) to 127 (right). Whole numbers, so a replay
+    /// steers exactly as the recording did.
+    pub move_x: i8,
+    /// Steering from -127 (down) to 127 (up).
+    pub move_y: i8,
+    pub fire: bool,
+    pub bomb: bool,
 }
 
-impl Team {
-    pub fn new(team: u8) -> Self {
-        Self { value: team }
+impl PlayerInput {
+    const FIRE: u8 = 1 << 4;
+    const BOMB: u8 = 1 << 5;
+
+    /// Steering from -1.0 to 1.0 on each axis.
+    pub fn move_axis(&self) -> Vec2 {
+        Vec2::new(self.move_x as f32, self.move_y as f32) / i8::MAX as f32
     }
-}
 
-#[derive(Component, Debug)]
-pub struct IsBullet {
-    pub value: bool,
-}
+    pub fn set_move_axis(&mut self, axis: Vec2) {
+        let steps = (axis.clamp(Vec2::NEG_ONE, Vec2::ONE) * i8::MAX as f32).round();
+        self.move_x = steps.x as i8;
+        self.move_y = steps.y as i8;
+    }
 
-impl IsBullet {
-    pub fn new(is_bullet: bool) -> Self {
-        Self { value: is_bullet }
+    /// The buttons held, as bits.
+    pub fn button_bits(self) -> u8 {
+        let mut bits = 0;
+        for (pressed, bit) in [(self.fire, Self::FIRE), (self.bomb, Self::BOMB)] {
+            if pressed {
+                bits |= bit;
+            }
+        }
+        bits
+    }
+
+    pub fn from_parts(button_bits: u8, move_x: i8, move_y: i8) -> Self {
+        Self {
+            move_x,
+            move_y,
+            fire: button_bits & Self::FIRE != 0,
+            bomb: button_bits & Self::BOMB != 0,
+        }
     }
 }
 
@@ -65,122 +111,247 @@ impl GameOverCountdown {
     }
 }
 
+/// Sent when the game-over countdown runs out. Leaving the round any other
+/// way, like restarting or quitting from the pause menu, doesn't count as
+/// a finished run.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct GameOver;
+
+/// How many lives each round starts with. Insert before `GamePlugin` to
+/// change it.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct StartingLives(pub u32);
+
+impl Default for StartingLives {
+    fn default() -> Self {
+        Self(3)
+    }
+}
+
+/// Lives left in the current round, including the one being played.
+#[derive(Resource, Debug, Default)]
+pub struct Lives {
+    pub remaining: u32,
+}
+
+#[derive(Component, Debug)]
+pub struct RespawnCountdown {
+    pub value: f32,
+}
+
+/// Only pickups can touch the entity while this lasts.
+#[derive(Component, Debug)]
+pub struct Invulnerable {
+    pub remaining: f32,
+}
+
 impl Plugin for FighterPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::Game), spawn_player)
+        app.add_event::<GameOver>()
+            .init_resource::<PlayerInput>()
+            .init_resource::<StartingLives>()
+            .init_resource::<Lives>()
+            .add_systems(OnEnter(AppState::Game), (reset_lives, spawn_player))
             .add_systems(
-                Update,
+                FixedUpdate,
                 (
-                    player_movement,
-                    spawn_player_bullet,
-                    handle_player_collisions,
-                    handle_player_bullet_collisions,
+                    read_player_input
+                        .in_set(GameplaySet::Input)
+                        .run_if(not(resource_exists::<ReplayPlayback>)),
+                    (spawn_player_bullet, respawn_player).in_set(GameplaySet::Spawn),
+                    (player_movement, blink_invulnerable).in_set(GameplaySet::Movement),
+                    (handle_player_death, handle_player_bullet_collisions)
+                        .in_set(GameplaySet::CollisionResponse),
                 )
                     .run_if(in_state(AppState::Game)),
             );
     }
 }
 
+fn reset_lives(mut lives: ResMut<Lives>, starting_lives: Res<StartingLives>) {
+    lives.remaining = starting_lives.0;
+}
+
 fn spawn_player(mut commands: Commands, scene_assets: Res<SceneAssets>) {
-    commands.spawn((
+    commands.spawn(player_bundle(&scene_assets, Vec3::ZERO));
+}
+
+fn player_bundle(scene_assets: &SceneAssets, position: Vec3) -> impl Bundle {
+    let weapon = Weapon::default();
+    (
         SpriteBundle {
             texture: scene_assets.player.image.clone_weak(),
-            transform: Transform::from_xyz(0.0, 0.0, 1.0),
+            transform: Transform::from_translation(position),
             ..default()
         },
         Player,
-        Position::new(Vec3::new(0., 0., 0.)),
-        Reload::new(PLAYER_RELOAD),
-        Collider::new(Size::new(scene_assets.player.dimensions)),
+        Position::new(position),
+        PlayerVelocity::default(),
+        Reload::new(weapon.reload),
+        weapon,
+        Collider::circle(PLAYER_HITBOX_RADIUS),
+        Health::new(PLAYER_HEALTH),
+        PowerUps::default(),
         Size::new(scene_assets.player.dimensions),
-        Team::new(1),
-        IsBullet::new(false),
-    ));
+        CollisionLayers::new(
+            Layer::PLAYER,
+            Layer::ENEMY | Layer::ENEMY_SHOT | Layer::PICKUP | Layer::HAZARD,
+        ),
+    )
 }
 
-fn player_movement(
+#[allow(clippy::type_complexity)]
+fn respawn_player(
+    mut commands: Commands,
     time: Res<Time>,
-    window: Query<&Window>,
-    mut query: Query<(&mut Transform, &mut Position), With<Player>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    bounds: Res<PlayfieldBounds>,
     scene_assets: Res<SceneAssets>,
+    mut countdown_query: Query<(Entity, &mut RespawnCountdown)>,
+    hostile_query: Query<&Position, Or<(With<Alien>, With<AlienBullet>)>>,
 ) {
-    if let Ok((mut transform, mut position)) = query.get_single_mut() {
-        let mut direction_x = 0.0;
-        let mut direction_y = 0.0;
+    let Ok((entity, mut countdown)) = countdown_query.get_single_mut() else {
+        return;
+    };
+    countdown.value -= time.delta_seconds();
+    if countdown.value > 0.0 {
+        return;
+    }
+    commands.entity(entity).despawn_recursive();
 
-        if keyboard_input.pressed(KeyCode::ArrowLeft) {
-            direction_x -= 1.0;
-        }
-        if keyboard_input.pressed(KeyCode::ArrowRight) {
-            direction_x += 1.0;
-        }
-        if keyboard_input.pressed(KeyCode::ArrowDown) {
-            direction_y -= 1.0;
-        }
-        if keyboard_input.pressed(KeyCode::ArrowUp) {
-            direction_y += 1.0;
+    // Come back on the left, in whichever lane is furthest from anything hostile.
+    let margin = scene_assets.player.dimensions.1 as f32;
+    let lanes = 7;
+    let position = (0..lanes)
+        .map(|lane| {
+            let t = lane as f32 / (lanes - 1) as f32;
+            let y = (bounds.height / 2.0 - margin) * (1.0 - 2.0 * t);
+            Vec3::new(-bounds.width / 4.0, y, 0.0)
+        })
+        .max_by(|a, b| {
+            let clearance = |spot: &Vec3| {
+                hostile_query
+                    .iter()
+                    .map(|hostile| hostile.value.distance_squared(*spot))
+                    .fold(f32::INFINITY, f32::min)
+            };
+            clearance(a).total_cmp(&clearance(b))
+        })
+        .unwrap();
+
+    commands.spawn((
+        player_bundle(&scene_assets, position),
+        Invulnerable {
+            remaining: RESPAWN_INVULNERABILITY,
+        },
+    ));
+}
+
+fn blink_invulnerable(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Invulnerable, &mut Visibility)>,
+) {
+    for (entity, mut invulnerable, mut visibility) in query.iter_mut() {
+        invulnerable.remaining -= time.delta_seconds();
+        if invulnerable.remaining <= 0.0 {
+            commands.entity(entity).remove::<Invulnerable>();
+            *visibility = Visibility::Inherited;
+        } else if (invulnerable.remaining * BLINKS_PER_SECOND) as u32 % 2 == 0 {
+            *visibility = Visibility::Inherited;
+        } else {
+            *visibility = Visibility::Hidden;
         }
+    }
+}
+
+fn read_player_input(actions: Res<ActionState>, mut player_input: ResMut<PlayerInput>) {
+    *player_input = PlayerInput {
+        fire: actions.pressed(Action::Fire),
+        bomb: actions.pressed(Action::Bomb),
+        ..default()
+    };
+    player_input.set_move_axis(actions.move_axis());
+}
+
+fn player_movement(
+    time: Res<Time>,
+    bounds: Res<PlayfieldBounds>,
+    mut query: Query<(&mut Position, &mut PlayerVelocity), With<Player>>,
+    player_input: Res<PlayerInput>,
+    scene_assets: Res<SceneAssets>,
+) {
+    if let Ok((mut position, mut velocity)) = query.get_single_mut() {
+        let start = position.value;
+        let direction = player_input.move_axis();
 
-        position.value.x += direction_x * PLAYER_SPEED * time.delta_seconds();
-        position.value.y += direction_y * PLAYER_SPEED * time.delta_seconds();
-        let window = window.single();
-        if position.value.x
-            < -(window.width() / 2.0 - scene_assets.player.dimensions.0 as f32 / 2.0)
+        position.value.x += direction.x * PLAYER_SPEED * time.delta_seconds();
+        position.value.y += direction.y * PLAYER_SPEED * time.delta_seconds();
+        if position.value.x < -(bounds.width / 2.0 - scene_assets.player.dimensions.0 as f32 / 2.0)
         {
             position.value.x =
-                -(window.width() / 2.0 - scene_assets.player.dimensions.0 as f32 / 2.0);
+                -(bounds.width / 2.0 - scene_assets.player.dimensions.0 as f32 / 2.0);
         }
 
         if position.value.x > 0.0 {
             position.value.x = 0.0;
         }
 
-        if position.value.y
-            < -(window.height() / 2.0 - scene_assets.player.dimensions.1 as f32 / 2.0)
+        if position.value.y < -(bounds.height / 2.0 - scene_assets.player.dimensions.1 as f32 / 2.0)
         {
             position.value.y =
-                -(window.height() / 2.0 - scene_assets.player.dimensions.1 as f32 / 2.0);
+                -(bounds.height / 2.0 - scene_assets.player.dimensions.1 as f32 / 2.0);
         }
 
-        if position.value.y
-            > (window.height() / 2.0 - scene_assets.player.dimensions.1 as f32 / 2.0)
+        if position.value.y > (bounds.height / 2.0 - scene_assets.player.dimensions.1 as f32 / 2.0)
         {
             position.value.y =
-                (window.height() / 2.0 - scene_assets.player.dimensions.1 as f32 / 2.0);
+                (bounds.height / 2.0 - scene_assets.player.dimensions.1 as f32 / 2.0);
         }
 
-        transform.translation = position.value;
+        velocity.value = (position.value - start).truncate() / time.delta_seconds();
     }
 }
 
 fn spawn_player_bullet(
     time: Res<Time>,
-    mut query: Query<(&Position, &mut Reload), With<Player>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut query: Query<(&Position, &mut Reload, &Weapon, &PowerUps), With<Player>>,
+    player_input: Res<PlayerInput>,
     mut commands: Commands,
     scene_assets: Res<SceneAssets>,
     scene_sounds: Res<SceneSounds>,
 ) {
-    if let Ok((position, mut reload)) = query.get_single_mut() {
+    if let Ok((position, mut reload, weapon, power_ups)) = query.get_single_mut() {
         reload.value -= 60.0 * time.delta_seconds();
 
-        if keyboard_input.pressed(KeyCode::KeyF) && reload.value <= 0.0 {
-            commands.spawn((
-                SpriteBundle {
-                    texture: scene_assets.player_bullet.image.clone_weak(),
-                    transform: Transform::from_translation(position.value),
-                    ..default()
-                },
-                PlayerBullet,
-                Velocity::new(Vec3::new(PLAYER_BULLET_SPEED, 0.0, 0.0)),
-                Position::new(position.value),
-                Collider::new(Size::new(scene_assets.player_bullet.dimensions)),
-                Size::new(scene_assets.player_bullet.dimensions),
-                Team::new(1),
-                IsBullet::new(true),
-            ));
-            reload.value = PLAYER_RELOAD;
+        if player_input.fire && reload.value <= 0.0 {
+            let sprite = scene_assets.projectile(weapon.projectile);
+            let size = sprite.size();
+            for (offset, direction) in weapon.volley() {
+                let start = position.value + offset.extend(0.0);
+                commands.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            flip_x: direction.x < 0.0,
+                            ..default()
+                        },
+                        texture: sprite.image.clone_weak(),
+                        transform: Transform::from_translation(start),
+                        ..default()
+                    },
+                    PlayerBullet,
+                    Velocity::new((direction * weapon.speed).extend(0.0)),
+                    Position::new(start),
+                    Collider::capsule_in(size),
+                    Damage::new(weapon.damage),
+                    Size::new((size.x as u32, size.y as u32)),
+                    CollisionLayers::new(Layer::PLAYER_SHOT, Layer::ENEMY | Layer::HAZARD),
+                ));
+            }
+            reload.value = if power_ups.rapid_fire > 0.0 {
+                weapon.reload / 2.0
+            } else {
+                weapon.reload
+            };
 
             commands.spawn(AudioBundle {
                 source: scene_sounds.player_fire.clone(),
@@ -194,55 +365,40 @@ fn spawn_player_bullet(
     }
 }
 
-fn handle_player_collisions(
+pub(crate) fn handle_player_death(
     mut commands: Commands,
-    query: Query<(Entity, &Collider, &Position), With<Player>>,
+    mut killed_events: EventReader<Killed>,
+    query: Query<&Position, With<Player>>,
     scene_sounds: Res<SceneSounds>,
     scene_assets: Res<SceneAssets>,
+    mut rng: ResMut<GameRng>,
+    mut lives: ResMut<Lives>,
 ) {
-    for (entity, collider, position) in query.iter() {
-        for &collided_entity in collider.colliding_entities.iter() {
-            // Asteroid collided with another asteroid.
-            if query.get(collided_entity).is_ok() {
-                continue;
-            }
-
-            for _ in 0..30 {
-                let explosion = Explosion::new(position.value.x, position.value.y);
-
-                let image = scene_assets.explosion.image.clone();
-
-                commands.spawn((
-                    SpriteBundle {
-                        sprite: Sprite {
-                            color: Color::Rgba {
-                                red: explosion.r,
-                                green: explosion.g,
-                                blue: explosion.b,
-                                alpha: explosion.a,
-                            },
-                            ..default()
-                        },
-                        texture: image,
-                        transform: Transform::from_xyz(explosion.x, explosion.y, 0.0),
-                        ..default()
-                    },
-                    Velocity::new(Vec3::new(explosion.dx, explosion.dy, 0.0)),
-                    Position::new(Vec3::new(explosion.x, explosion.y, 0.0)),
-                    explosion,
-                ));
-            }
+    for killed in killed_events.read() {
+        let Ok(position) = query.get(killed.entity) else {
+            continue;
+        };
+        for _ in 0..30 {
+            let explosion = Explosion::new(position.value.x, position.value.y, &mut rng.cosmetic);
+            spawn_explosion(&mut commands, &scene_assets, explosion);
+        }
 
-            // Despawn the player.
-            commands.entity(entity).despawn_recursive();
-            commands.spawn(AudioBundle {
-                source: scene_sounds.player_dies.clone(),
-                settings: PlaybackSettings {
-                    mode: bevy::audio::PlaybackMode::Remove,
-                    ..default()
-                },
+        // Despawn the player.
+        commands.entity(killed.entity).despawn_recursive();
+        commands.spawn(AudioBundle {
+            source: scene_sounds.player_dies.clone(),
+            settings: PlaybackSettings {
+                mode: bevy::audio::PlaybackMode::Remove,
                 ..default()
+            },
+            ..default()
+        });
+        lives.remaining = lives.remaining.saturating_sub(1);
+        if lives.remaining > 0 {
+            commands.spawn(RespawnCountdown {
+                value: RESPAWN_DELAY,
             });
+        } else {
             commands.spawn(GameOverCountdown::new());
         }
     }
@@ -250,16 +406,18 @@ fn handle_player_collisions(
 
 fn handle_player_bullet_collisions(
     mut commands: Commands,
-    query: Query<(Entity, &Collider), With<PlayerBullet>>,
+    mut collision_events: EventReader<CollisionEvent>,
+    query: Query<(), With<PlayerBullet>>,
 ) {
-    for (entity, collider) in query.iter() {
-        for &collided_entity in collider.colliding_entities.iter() {
-            // Asteroid collided with another asteroid.
-            if query.get(collided_entity).is_ok() {
-                continue;
+    let mut spent = HashSet::new();
+    for event in collision_events.read() {
+        if event.phase != CollisionPhase::Enter {
+            continue;
+        }
+        for (entity, _) in event.pairs() {
+            if query.contains(entity) && spent.insert(entity) {
+                commands.entity(entity).despawn_recursive();
             }
-            // Despawn the asteroid.
-            commands.entity(entity).despawn_recursive();
         }
     }
 }
@@ -0,0 +1,150 @@
+use bevy::{
+    input::gamepad::{GamepadConnection, GamepadConnectionEvent, GamepadSettings},
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::actions::{Action, ActionSet, ActionState, InputDevice};
+
+/// Stick movement below this is ignored, so a worn stick doesn't drift.
+/// Bevy's own per-axis deadzone is switched off so this is the only one.
+const STICK_DEADZONE: f32 = 0.2;
+
+/// Which gamepad buttons trigger each action, on every connected gamepad.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct GamepadBindings {
+    pub buttons: HashMap<Action, Vec<GamepadButtonType>>,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        let buttons = [
+            (Action::MoveUp, vec![GamepadButtonType::DPadUp]),
+            (Action::MoveDown, vec![GamepadButtonType::DPadDown]),
+            (Action::MoveLeft, vec![GamepadButtonType::DPadLeft]),
+            (Action::MoveRight, vec![GamepadButtonType::DPadRight]),
+            (
+                Action::Fire,
+                vec![GamepadButtonType::South, GamepadButtonType::West],
+            ),
+            (
+                Action::Bomb,
+                vec![GamepadButtonType::East, GamepadButtonType::RightTrigger],
+            ),
+            (Action::Pause, vec![GamepadButtonType::Start]),
+            (Action::Confirm, vec![GamepadButtonType::South]),
+            (Action::Back, vec![GamepadButtonType::East]),
+            (Action::Highscores, vec![GamepadButtonType::North]),
+            (Action::Controls, vec![GamepadButtonType::Select]),
+        ];
+        Self {
+            buttons: buttons.into_iter().collect(),
+        }
+    }
+}
+
+/// The name printed on a button, using the Xbox layout.
+pub fn button_label(button: GamepadButtonType) -> &'static str {
+    match button {
+        GamepadButtonType::South => "A",
+        GamepadButtonType::East => "B",
+        GamepadButtonType::West => "X",
+        GamepadButtonType::North => "Y",
+        GamepadButtonType::LeftTrigger => "LB",
+        GamepadButtonType::RightTrigger => "RB",
+        GamepadButtonType::LeftTrigger2 => "LT",
+        GamepadButtonType::RightTrigger2 => "RT",
+        GamepadButtonType::Select => "BACK",
+        GamepadButtonType::Start => "START",
+        GamepadButtonType::DPadUp => "D-PAD UP",
+        GamepadButtonType::DPadDown => "D-PAD DOWN",
+        GamepadButtonType::DPadLeft => "D-PAD LEFT",
+        GamepadButtonType::DPadRight => "D-PAD RIGHT",
+        _ => "?",
+    }
+}
+
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GamepadBindings>()
+            .add_systems(Startup, disable_axis_deadzone)
+            .add_systems(
+                PreUpdate,
+                (track_connections, read_gamepads).chain().in_set(ActionSet),
+            );
+    }
+}
+
+/// The stick deadzone is applied radially in `read_gamepads`. Leaving Bevy's
+/// square one on as well would eat the start of every push twice.
+fn disable_axis_deadzone(mut settings: ResMut<GamepadSettings>) {
+    let axis = &mut settings.default_axis_settings;
+    axis.set_deadzone_lowerbound(0.0);
+    axis.set_deadzone_upperbound(0.0);
+}
+
+/// Gamepads can come and go at any time. Prompts go back to the keyboard
+/// once the last one is unplugged.
+fn track_connections(
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+    gamepads: Res<Gamepads>,
+    mut device: ResMut<InputDevice>,
+) {
+    for event in connection_events.read() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => {
+                info!("Gamepad {} connected: {}", event.gamepad.id, info.name);
+            }
+            GamepadConnection::Disconnected => {
+                info!("Gamepad {} disconnected", event.gamepad.id);
+                if gamepads.iter().next().is_none() {
+                    device.set_if_neq(InputDevice::Keyboard);
+                }
+            }
+        }
+    }
+}
+
+fn read_gamepads(
+    gamepads: Res<Gamepads>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    bindings: Res<GamepadBindings>,
+    mut state: ResMut<ActionState>,
+    mut device: ResMut<InputDevice>,
+) {
+    for gamepad in gamepads.iter() {
+        let mut used = false;
+        for (action, types) in bindings.buttons.iter() {
+            let mut bound = types
+                .iter()
+                .map(|button_type| GamepadButton::new(gamepad, *button_type));
+            if bound.clone().any(|button| buttons.pressed(button)) {
+                state.press(*action);
+            }
+            used |= bound.any(|button| buttons.just_pressed(button));
+        }
+
+        let axis = |axis_type| {
+            axes.get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+        let stick = Vec2::new(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        );
+        // Rescale past the deadzone so the stick still starts from zero.
+        let length = stick.length();
+        if length > STICK_DEADZONE {
+            let scaled = ((length - STICK_DEADZONE) / (1.0 - STICK_DEADZONE)).min(1.0);
+            state.push_axis(stick / length * scaled);
+            used = true;
+        }
+
+        if used {
+            device.set_if_neq(InputDevice::Gamepad);
+        }
+    }
+}
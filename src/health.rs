@@ -0,0 +1,160 @@
+use bevy::prelude::*;
+
+use crate::{
+    collisions::{CollisionEvent, CollisionPhase},
+    AppState, GameplaySet,
+};
+
+const HIT_FLASH_DURATION: f32 = 0.1;
+const HIT_FLASH_COLOR: Color = Color::rgb(1.0, 0.35, 0.35);
+
+#[derive(Component, Debug)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Health {
+    pub fn new(max: u32) -> Self {
+        Self { current: max, max }
+    }
+}
+
+/// How much health this entity takes from whatever it hits.
+#[derive(Component, Debug)]
+pub struct Damage {
+    pub value: u32,
+}
+
+impl Damage {
+    pub fn new(value: u32) -> Self {
+        Self { value }
+    }
+}
+
+/// Points added to the score when this entity is killed.
+#[derive(Component, Debug)]
+pub struct ScoreValue {
+    pub value: u32,
+}
+
+impl ScoreValue {
+    pub fn new(value: u32) -> Self {
+        Self { value }
+    }
+}
+
+/// Tints the sprite for a moment after a hit that did not kill.
+#[derive(Component, Debug)]
+pub struct HitFlash {
+    remaining: f32,
+    /// The sprite's own colour, put back when the flash ends.
+    restore: Option<Color>,
+}
+
+impl HitFlash {
+    /// Changes the colour put back when the flash ends, for sprites that
+    /// are retinted while flashing.
+    pub fn set_restore(&mut self, color: Color) {
+        self.restore = Some(color);
+    }
+}
+
+/// Takes no damage while present.
+#[derive(Component, Debug)]
+pub struct Shielded;
+
+/// Sent once when an entity's health reaches zero. The entity is left for
+/// its own plugin to despawn.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct Killed {
+    pub entity: Entity,
+    pub by: Entity,
+}
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Killed>()
+            .add_systems(
+                FixedUpdate,
+                apply_damage
+                    .in_set(GameplaySet::Damage)
+                    .run_if(in_state(AppState::Game)),
+            )
+            .add_systems(Update, fade_hit_flash);
+    }
+}
+
+pub(crate) fn apply_damage(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut health_query: Query<(&mut Health, Option<&mut HitFlash>), Without<Shielded>>,
+    damage_query: Query<&Damage>,
+    mut killed_events: EventWriter<Killed>,
+) {
+    for event in collision_events.read() {
+        if event.phase != CollisionPhase::Enter {
+            continue;
+        }
+        for (entity, other) in event.pairs() {
+            let (Ok((mut health, flash)), Ok(damage)) =
+                (health_query.get_mut(entity), damage_query.get(other))
+            else {
+                continue;
+            };
+            deal_damage(
+                &mut commands,
+                &mut killed_events,
+                (entity, &mut health, flash),
+                other,
+                damage.value,
+            );
+        }
+    }
+}
+
+/// Takes `amount` off `target`'s health, flashing it or sending `Killed`.
+pub(crate) fn deal_damage(
+    commands: &mut Commands,
+    killed_events: &mut EventWriter<Killed>,
+    target: (Entity, &mut Health, Option<Mut<HitFlash>>),
+    by: Entity,
+    amount: u32,
+) {
+    let (entity, health, flash) = target;
+    // Already dead from an earlier hit this tick.
+    if health.current == 0 {
+        return;
+    }
+
+    health.current = health.current.saturating_sub(amount);
+    if health.current == 0 {
+        killed_events.send(Killed { entity, by });
+    } else if let Some(mut flash) = flash {
+        flash.remaining = HIT_FLASH_DURATION;
+    } else {
+        commands.entity(entity).insert(HitFlash {
+            remaining: HIT_FLASH_DURATION,
+            restore: None,
+        });
+    }
+}
+
+fn fade_hit_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut HitFlash, &mut Sprite)>,
+) {
+    for (entity, mut flash, mut sprite) in query.iter_mut() {
+        let restore = *flash.restore.get_or_insert(sprite.color);
+        flash.remaining -= time.delta_seconds();
+        if flash.remaining <= 0.0 {
+            sprite.color = restore;
+            commands.entity(entity).remove::<HitFlash>();
+        } else {
+            sprite.color = HIT_FLASH_COLOR;
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::{Action, ActionState, InputDevice, Prompts},
+    fighter::{GameOver, Player},
+    hud::{setup_hud, CurrentScore, Highscore},
+    storage::Storage,
+    AppState, TICK_RATE_HZ,
+};
+
+const STORAGE_KEY: &str = "highscores";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighscoreEntry {
+    pub score: u32,
+    /// Seconds since the Unix epoch.
+    pub recorded_at: u64,
+    /// How many gameplay ticks the player survived.
+    pub ticks: u32,
+}
+
+/// The best runs so far, highest score first.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighscoreTable {
+    entries: Vec<HighscoreEntry>,
+}
+
+impl HighscoreTable {
+    pub const MAX_ENTRIES: usize = 10;
+
+    pub fn entries(&self) -> &[HighscoreEntry] {
+        &self.entries
+    }
+
+    pub fn best(&self) -> u32 {
+        self.entries.first().map_or(0, |entry| entry.score)
+    }
+
+    /// Returns the entry's rank, or `None` if it did not make the table.
+    /// Ties go to whoever got there first.
+    pub fn insert(&mut self, entry: HighscoreEntry) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|existing| existing.score < entry.score)
+            .unwrap_or(self.entries.len());
+        if rank >= Self::MAX_ENTRIES {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(Self::MAX_ENTRIES);
+        Some(rank)
+    }
+}
+
+/// Ticks survived in the current round.
+#[derive(Resource, Debug, Default)]
+struct RunTicks(u32);
+
+/// Rank of the entry added by the round that just ended, if it placed.
+#[derive(Resource, Debug, Default)]
+struct LatestRank(Option<usize>);
+
+#[derive(Component, Debug)]
+struct HighscoresScreen;
+
+#[derive(Component, Debug)]
+struct PromptText;
+
+pub struct HighscoresPlugin;
+
+impl Plugin for HighscoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HighscoreTable>()
+            .init_resource::<RunTicks>()
+            .init_resource::<LatestRank>()
+            .add_systems(Startup, (load_highscores, sync_highscore).chain())
+            .add_systems(
+                OnEnter(AppState::Game),
+                (reset_run_ticks, sync_highscore.before(setup_hud)),
+            )
+            .add_systems(
+                FixedUpdate,
+                count_run_ticks.run_if(in_state(AppState::Game)),
+            )
+            .add_systems(
+                OnExit(AppState::Game),
+                (record_score, sync_highscore).chain(),
+            )
+            .add_systems(OnEnter(AppState::Highscores), setup)
+            .add_systems(
+                Update,
+                (leave, update_prompt).run_if(in_state(AppState::Highscores)),
+            )
+            .add_systems(OnExit(AppState::Highscores), despawn);
+    }
+}
+
+fn load_highscores(storage: Res<Storage>, mut table: ResMut<HighscoreTable>) {
+    if let Some(stored) = storage.load(STORAGE_KEY) {
+        *table = stored;
+    }
+}
+
+/// Puts the HUD's best back to the table's, dropping the score of a round
+/// that ended without being recorded.
+fn sync_highscore(table: Res<HighscoreTable>, mut highscore: ResMut<Highscore>) {
+    highscore.update(table.best());
+}
+
+fn reset_run_ticks(mut run_ticks: ResMut<RunTicks>, mut latest_rank: ResMut<LatestRank>) {
+    run_ticks.0 = 0;
+    latest_rank.0 = None;
+}
+
+fn count_run_ticks(mut run_ticks: ResMut<RunTicks>, player_query: Query<(), With<Player>>) {
+    if !player_query.is_empty() {
+        run_ticks.0 += 1;
+    }
+}
+
+/// Only a round that ended in game over makes the table.
+fn record_score(
+    mut game_over_events: EventReader<GameOver>,
+    score: Res<CurrentScore>,
+    run_ticks: Res<RunTicks>,
+    mut table: ResMut<HighscoreTable>,
+    mut latest_rank: ResMut<LatestRank>,
+    mut storage: ResMut<Storage>,
+) {
+    if game_over_events.read().count() == 0 || score.value == 0 {
+        return;
+    }
+    let recorded_at = web_time::SystemTime::now()
+        .duration_since(web_time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    latest_rank.0 = table.insert(HighscoreEntry {
+        score: score.value,
+        recorded_at,
+        ticks: run_ticks.0,
+    });
+    if latest_rank.0.is_some() {
+        storage.save(STORAGE_KEY, &*table);
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    table: Res<HighscoreTable>,
+    latest_rank: Res<LatestRank>,
+    prompts: Prompts,
+) {
+    let font = asset_server.load("fonts/Orbitron-VariableFont_wght.ttf");
+    let title_style = TextStyle {
+        font: font.clone(),
+        font_size: 50.0,
+        ..default()
+    };
+    let row_style = TextStyle {
+        font,
+        font_size: 28.0,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            HighscoresScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text::from_section("HIGH SCORES", title_style.clone()),
+                style: Style {
+                    margin: UiRect::bottom(Val::Px(30.0)),
+                    ..default()
+                },
+                ..default()
+            });
+
+            if table.entries().is_empty() {
+                parent.spawn(TextBundle::from_section("NO SCORES YET", row_style.clone()));
+            }
+
+            for (rank, entry) in table.entries().iter().enumerate() {
+                let mut style = row_style.clone();
+                if latest_rank.0 == Some(rank) {
+                    style.color = Color::GREEN;
+                }
+                let seconds = (entry.ticks as f64 / TICK_RATE_HZ) as u64;
+                parent.spawn(TextBundle::from_section(
+                    format!(
+                        "{:>2}.  {:>6}   {}   {}:{:02}",
+                        rank + 1,
+                        entry.score,
+                        format_date(entry.recorded_at),
+                        seconds / 60,
+                        seconds % 60
+                    ),
+                    style,
+                ));
+            }
+
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(prompt(&prompts), row_style),
+                    style: Style {
+                        margin: UiRect::top(Val::Px(30.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                PromptText,
+            ));
+        });
+}
+
+fn prompt(prompts: &Prompts) -> String {
+    if prompts.device() == InputDevice::Touch {
+        return String::from("TAP TO CONTINUE");
+    }
+    format!("PRESS FIRE ( {} ) TO CONTINUE", prompts.label(Action::Fire))
+}
+
+/// Follows the player switching between keyboard and gamepad.
+fn update_prompt(prompts: Prompts, mut query: Query<&mut Text, With<PromptText>>) {
+    let text = prompt(&prompts);
+    for mut prompt_text in query.iter_mut() {
+        if prompt_text.sections[0].value != text {
+            prompt_text.sections[0].value.clone_from(&text);
+        }
+    }
+}
+
+fn leave(mut next_state: ResMut<NextState<AppState>>, actions: Res<ActionState>) {
+    if [Action::Fire, Action::Confirm, Action::Back]
+        .into_iter()
+        .any(|action| actions.just_pressed(action))
+    {
+        next_state.set(AppState::IntroScreen);
+    }
+}
+
+fn despawn(mut commands: Commands, query: Query<Entity, With<HighscoresScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Formats a Unix timestamp as a UTC `YYYY-MM-DD` date.
+fn format_date(unix_seconds: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm.
+    let days = (unix_seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(score: u32) -> HighscoreEntry {
+        HighscoreEntry {
+            score,
+            recorded_at: 0,
+            ticks: score,
+        }
+    }
+
+    fn scores(table: &HighscoreTable) -> Vec<u32> {
+        table.entries().iter().map(|entry| entry.score).collect()
+    }
+
+    #[test]
+    fn insert_keeps_highest_first_and_returns_rank() {
+        let mut table = HighscoreTable::default();
+        assert_eq!(table.insert(entry(50)), Some(0));
+        assert_eq!(table.insert(entry(80)), Some(0));
+        assert_eq!(table.insert(entry(10)), Some(2));
+        assert_eq!(table.insert(entry(60)), Some(1));
+        assert_eq!(scores(&table), [80, 60, 50, 10]);
+        assert_eq!(table.best(), 80);
+    }
+
+    #[test]
+    fn ties_go_to_the_earlier_run() {
+        let mut table = HighscoreTable::default();
+        table.insert(HighscoreEntry {
+            recorded_at: 1,
+            ..entry(50)
+        });
+        assert_eq!(
+            table.insert(HighscoreEntry {
+                recorded_at: 2,
+                ..entry(50)
+            }),
+            Some(1)
+        );
+        assert_eq!(table.entries()[0].recorded_at, 1);
+    }
+
+    #[test]
+    fn table_is_capped() {
+        let mut table = HighscoreTable::default();
+        for score in 1..=HighscoreTable::MAX_ENTRIES as u32 {
+            table.insert(entry(score * 10));
+        }
+        assert_eq!(table.entries().len(), HighscoreTable::MAX_ENTRIES);
+
+        // Too low to place, so nothing changes.
+        assert_eq!(table.insert(entry(5)), None);
+        assert_eq!(table.insert(entry(10)), None);
+        assert_eq!(table.entries().len(), HighscoreTable::MAX_ENTRIES);
+
+        // A new best pushes the lowest entry out.
+        assert_eq!(table.insert(entry(1000)), Some(0));
+        assert_eq!(table.entries().len(), HighscoreTable::MAX_ENTRIES);
+        assert_eq!(table.best(), 1000);
+        assert_eq!(table.entries().last().unwrap().score, 20);
+    }
+
+    #[test]
+    fn table_round_trips_through_storage() {
+        let mut table = HighscoreTable::default();
+        table.insert(entry(30));
+        table.insert(entry(70));
+
+        let mut storage = Storage::in_memory();
+        storage.save(STORAGE_KEY, &table);
+        let loaded: HighscoreTable = storage.load(STORAGE_KEY).unwrap();
+        assert_eq!(scores(&loaded), [70, 30]);
+    }
+
+    fn finish_round(app: &mut App, score: u32, game_over: bool) {
+        app.world
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Game);
+        app.update();
+        app.world.resource_mut::<CurrentScore>().value = score;
+        app.update();
+        assert_eq!(app.world.resource::<Highscore>().value, score);
+
+        if game_over {
+            app.world.send_event(GameOver);
+        }
+        app.world
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::IntroScreen);
+        app.update();
+    }
+
+    #[test]
+    fn hud_best_only_keeps_recorded_rounds() {
+        let mut app = crate::headless_app(Some(1));
+        while *app.world.resource::<State<AppState>>().get() == AppState::Loading {
+            app.update();
+        }
+
+        finish_round(&mut app, 40, true);
+        assert_eq!(app.world.resource::<Highscore>().value, 40);
+
+        // Quitting isn't recorded, so the HUD goes back to the table's best.
+        finish_round(&mut app, 90, false);
+        assert_eq!(app.world.resource::<HighscoreTable>().best(), 40);
+        assert_eq!(app.world.resource::<Highscore>().value, 40);
+    }
+}
@@ -1,6 +1,12 @@
 use bevy::prelude::*;
 
-use crate::AppState;
+use crate::{
+    bombs::Bombs,
+    fighter::{Lives, Player},
+    pickups::PowerUps,
+    weapons::Weapon,
+    AppState,
+};
 
 #[derive(Resource, Debug, Default)]
 pub struct CurrentScore {
@@ -38,10 +44,20 @@ impl Plugin for HudPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<CurrentScore>()
             .init_resource::<Highscore>()
-            .add_systems(OnEnter(AppState::Game), setup_hud)
+            .add_systems(
+                OnEnter(AppState::Game),
+                (reset_current_score, setup_hud).chain(),
+            )
             .add_systems(
                 Update,
-                (update_current_score, update_highscore_score).run_if(in_state(AppState::Game)),
+                (
+                    update_current_score,
+                    update_lives,
+                    update_bombs,
+                    update_effects,
+                    update_highscore_score,
+                )
+                    .run_if(in_state(AppState::Game)),
             )
             .add_systems(OnExit(AppState::Game), despawn_hud);
     }
@@ -50,15 +66,30 @@ impl Plugin for HudPlugin {
 #[derive(Component, Debug)]
 pub struct ScoreText;
 
+#[derive(Component, Debug)]
+pub struct LivesText;
+
+#[derive(Component, Debug)]
+pub struct BombsText;
+
+#[derive(Component, Debug)]
+pub struct EffectsText;
+
 #[derive(Component, Debug)]
 pub struct HighscoreText;
 
 #[derive(Component, Debug)]
 pub struct Hud;
 
-fn setup_hud(
+fn reset_current_score(mut score: ResMut<CurrentScore>) {
+    score.reset();
+}
+
+pub(crate) fn setup_hud(
     mut commands: Commands,
     current_score: Res<CurrentScore>,
+    lives: Res<Lives>,
+    bombs: Res<Bombs>,
     highscore: Res<Highscore>,
     asset_server: Res<AssetServer>,
 ) {
@@ -97,6 +128,39 @@ fn setup_hud(
                 },
                 ScoreText,
             ));
+            parent.spawn((
+                TextBundle {
+                    text: Text {
+                        sections: vec![
+                            TextSection::new("Lives: ", text_style.clone()),
+                            TextSection::new(format!("{}", lives.remaining), text_style.clone()),
+                        ],
+                        ..default()
+                    },
+                    ..default()
+                },
+                LivesText,
+            ));
+            parent.spawn((
+                TextBundle {
+                    text: Text {
+                        sections: vec![
+                            TextSection::new("Bombs: ", text_style.clone()),
+                            TextSection::new(format!("{}", bombs.remaining), text_style.clone()),
+                        ],
+                        ..default()
+                    },
+                    ..default()
+                },
+                BombsText,
+            ));
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section("", text_style.clone()),
+                    ..default()
+                },
+                EffectsText,
+            ));
             parent.spawn((
                 TextBundle {
                     text: Text {
@@ -124,17 +188,60 @@ fn update_current_score(
     }
 }
 
+fn update_lives(mut text_query: Query<&mut Text, With<LivesText>>, lives: Res<Lives>) {
+    if lives.is_changed() {
+        for mut text in text_query.iter_mut() {
+            text.sections[1].value = format!("{}", lives.remaining);
+        }
+    }
+}
+
+fn update_bombs(mut text_query: Query<&mut Text, With<BombsText>>, bombs: Res<Bombs>) {
+    if bombs.is_changed() {
+        for mut text in text_query.iter_mut() {
+            text.sections[1].value = format!("{}", bombs.remaining);
+        }
+    }
+}
+
+fn update_effects(
+    mut text_query: Query<&mut Text, With<EffectsText>>,
+    player_query: Query<(&Weapon, &PowerUps), With<Player>>,
+) {
+    let effects = player_query
+        .get_single()
+        .map(|(weapon, power_ups)| {
+            std::iter::once(format!("{} Lv{}", weapon.kind.name(), weapon.level))
+                .chain(
+                    power_ups
+                        .active()
+                        .map(|(name, remaining)| format!("{name} {}", remaining.ceil())),
+                )
+                .collect::<Vec<_>>()
+                .join("  ")
+        })
+        .unwrap_or_default();
+    for mut text in text_query.iter_mut() {
+        if text.sections[0].value != effects {
+            text.sections[0].value.clone_from(&effects);
+        }
+    }
+}
+
+/// The running score counts as the best while the round lasts. Only a
+/// recorded round keeps it once the round is over.
 fn update_highscore_score(
     mut text_query: Query<&mut Text, With<HighscoreText>>,
     current_score: Res<CurrentScore>,
     mut highscore: ResMut<Highscore>,
 ) {
-    if current_score.value > highscore.value {
+    let beaten = current_score.value > highscore.value;
+    if beaten {
         highscore.value = current_score.value;
     }
     for mut text in text_query.iter_mut() {
         text.sections[1].value = format!("{}", highscore.value.to_string());
-        if highscore.is_changed() {
+        if beaten {
             text.sections[0].style.color = Color::GREEN;
             text.sections[1].style.color = Color::GREEN;
         }
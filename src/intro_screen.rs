@@ -1,13 +1,20 @@
 use bevy::prelude::*;
 
-use crate::AppState;
+use crate::{
+    actions::{Action, ActionState, InputDevice, Prompts},
+    AppState,
+};
 
 pub struct IntroScreenPlugin;
 
 impl Plugin for IntroScreenPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(AppState::IntroScreen), setup)
-            .add_systems(Update, start_game.run_if(in_state(AppState::IntroScreen)))
+            .add_systems(
+                Update,
+                (start_game, update_prompt, highlight_buttons)
+                    .run_if(in_state(AppState::IntroScreen)),
+            )
             .add_systems(OnExit(AppState::IntroScreen), despawn);
     }
 }
@@ -15,12 +22,58 @@ impl Plugin for IntroScreenPlugin {
 #[derive(Component, Debug)]
 pub struct UiComponent;
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+#[derive(Component, Debug)]
+struct PromptText;
+
+const BUTTON_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.1);
+const HOVERED_COLOR: Color = Color::rgba(0.35, 0.75, 0.35, 0.6);
+
+/// Clickable and tappable way to the other screens, for when there is no
+/// keyboard.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+struct TitleButton(Action);
+
+impl TitleButton {
+    const ALL: [TitleButton; 2] = [
+        TitleButton(Action::Highscores),
+        TitleButton(Action::Controls),
+    ];
+
+    fn label(&self, prompts: &Prompts) -> String {
+        let name = match self.0 {
+            Action::Highscores => "HIGH SCORES",
+            _ => "CONTROLS",
+        };
+        if prompts.device() == InputDevice::Touch {
+            return String::from(name);
+        }
+        format!("{name} ( {} )", prompts.label(self.0))
+    }
+}
+
+/// The text inside a [`TitleButton`].
+#[derive(Component, Debug)]
+struct TitleButtonText(TitleButton);
+
+fn prompt(prompts: &Prompts) -> String {
+    if prompts.device() == InputDevice::Touch {
+        return String::from("\nTAP TO PLAY!");
+    }
+    format!("\nPRESS FIRE ( {} ) TO PLAY!", prompts.label(Action::Fire))
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, prompts: Prompts) {
+    let font = asset_server.load("fonts/Orbitron-VariableFont_wght.ttf");
     let text_style = TextStyle {
-        font: asset_server.load("fonts/Orbitron-VariableFont_wght.ttf"),
+        font: font.clone(),
         font_size: 50.0,
         ..default()
     };
+    let button_style = TextStyle {
+        font,
+        font_size: 28.0,
+        ..default()
+    };
     let image = asset_server.load("titleText.png");
 
     commands
@@ -52,27 +105,100 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                     ..default()
                 }),
             );
-            parent.spawn(TextBundle {
-                text: Text::from_section(
-                    String::from("\nPRESS FIRE ( F ) TO PLAY!"),
-                    text_style.clone(),
-                ),
-                style: Style {
-                    padding: UiRect::all(Val::Px(50.0)),
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(prompt(&prompts), text_style.clone())
+                        .with_justify(JustifyText::Center),
+                    style: Style {
+                        padding: UiRect::all(Val::Px(50.0)),
+                        ..default()
+                    },
                     ..default()
                 },
-                ..default()
+                PromptText,
+            ));
+            parent.spawn(NodeBundle::default()).with_children(|parent| {
+                for button in TitleButton::ALL {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::axes(Val::Px(20.0), Val::Px(10.0)),
+                                    margin: UiRect::horizontal(Val::Px(15.0)),
+                                    ..default()
+                                },
+                                background_color: BUTTON_COLOR.into(),
+                                ..default()
+                            },
+                            button,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    button.label(&prompts),
+                                    button_style.clone(),
+                                ),
+                                TitleButtonText(button),
+                            ));
+                        });
+                }
             });
         });
 }
 
 fn start_game(
     mut next_state: ResMut<NextState<AppState>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
+    buttons: Query<(&Interaction, &TitleButton), Changed<Interaction>>,
 ) {
-    if keyboard_input.pressed(KeyCode::KeyF) {
+    let clicked = |action| {
+        buttons
+            .iter()
+            .any(|(interaction, button)| *interaction == Interaction::Pressed && button.0 == action)
+    };
+    if actions.just_pressed(Action::Fire) || actions.just_pressed(Action::Confirm) {
         next_state.set(AppState::Game);
         println!("changed state to Game.");
+    } else if actions.just_pressed(Action::Highscores) || clicked(Action::Highscores) {
+        next_state.set(AppState::Highscores);
+    } else if actions.just_pressed(Action::Controls) || clicked(Action::Controls) {
+        next_state.set(AppState::Controls);
+    }
+}
+
+/// Follows the player switching between keyboard and gamepad.
+#[allow(clippy::type_complexity)]
+fn update_prompt(
+    prompts: Prompts,
+    mut query: Query<
+        (&mut Text, Option<&TitleButtonText>),
+        Or<(With<PromptText>, With<TitleButtonText>)>,
+    >,
+) {
+    for (mut text, button) in query.iter_mut() {
+        let value = match button {
+            Some(TitleButtonText(button)) => button.label(&prompts),
+            None => prompt(&prompts),
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn highlight_buttons(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<TitleButton>),
+    >,
+) {
+    for (interaction, mut background) in buttons.iter_mut() {
+        *background = match interaction {
+            Interaction::None => BUTTON_COLOR,
+            Interaction::Hovered | Interaction::Pressed => HOVERED_COLOR,
+        }
+        .into();
     }
 }
 
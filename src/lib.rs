@@ -1,22 +1,83 @@
+mod actions;
+mod alien_kinds;
 mod aliens;
+mod bombs;
+mod boss;
+mod bullet_patterns;
 mod collisions;
+mod controls;
 mod explosions;
 mod fighter;
+mod gamepad;
+mod health;
+mod highscores;
 mod hud;
 mod intro_screen;
+mod mouse;
 mod movement;
+mod pause;
+mod pickups;
+mod replay;
+mod rng;
 mod scene;
+mod spatial_hash;
+mod stage;
+mod storage;
+mod touch;
+mod weapons;
 
-use bevy::prelude::*;
+use std::time::Duration;
 
+use bevy::{input::InputPlugin, prelude::*, time::TimeUpdateStrategy};
+
+use actions::ActionsPlugin;
 use aliens::AliensPlugin;
+use bombs::BombsPlugin;
+use boss::BossPlugin;
+use bullet_patterns::BulletPatternsPlugin;
 use collisions::CollisionDetectionPlugin;
+use controls::ControlsPlugin;
 use explosions::ExplosionsPlugin;
 use fighter::FighterPlugin;
+use gamepad::GamepadPlugin;
+use health::HealthPlugin;
+use highscores::HighscoresPlugin;
 use hud::HudPlugin;
 use intro_screen::IntroScreenPlugin;
+use mouse::MousePlugin;
 use movement::MovementPlugin;
+use pause::PausePlugin;
+use pickups::PickupsPlugin;
+use replay::ReplayPlugin;
+use rng::RngPlugin;
 use scene::SceneLoaderPlugin;
+use stage::StagePlugin;
+use storage::StoragePlugin;
+use touch::TouchPlugin;
+
+pub use actions::{Action, ActionSet, ActionState, InputDevice, KeyBindings};
+pub use alien_kinds::{AlienKind, UnknownAlienKind};
+pub use aliens::SpawnAlien;
+pub use bullet_patterns::{aimed_direction, leading_direction, BulletPattern, Shot};
+pub use collisions::{
+    Collider, ColliderShape, CollisionEvent, CollisionLayers, CollisionPhase, Layer,
+    PixelPerfectCollisions,
+};
+pub use fighter::PlayerInput;
+pub use gamepad::GamepadBindings;
+pub use health::{Damage, Health, Killed, ScoreValue};
+pub use highscores::{HighscoreEntry, HighscoreTable};
+pub use hud::CurrentScore;
+pub use mouse::SteeringMode;
+pub use pause::PauseState;
+pub use pickups::{PickupKind, PowerUps};
+pub use replay::{Replay, ReplayError, ReplayPlayback, ReplayRecorder};
+pub use rng::GameRng;
+pub use scene::PlayfieldBounds;
+pub use spatial_hash::SpatialHash;
+pub use stage::{Formation, Stage, StageAction, StageEvent, StageLoaderError};
+pub use storage::{Storage, StorageBackend, StorageError};
+pub use weapons::{Projectile, Weapon, WeaponKind};
 
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
@@ -25,28 +86,156 @@ use wasm_bindgen::prelude::*;
 #[wasm_bindgen(start)]
 pub fn run_app() {
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
-    main();
+    windowed_app().run();
 }
 
-fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
-        .init_state::<AppState>()
-        .add_plugins(IntroScreenPlugin)
-        .add_plugins(SceneLoaderPlugin)
-        .add_plugins(HudPlugin)
-        .add_plugins(MovementPlugin)
-        .add_plugins(FighterPlugin)
-        .add_plugins(AliensPlugin)
-        .add_plugins(ExplosionsPlugin)
-        .add_plugins(CollisionDetectionPlugin)
-        .run();
+/// Builds the game with a window, renderer and audio, configured from the
+/// command line.
+pub fn windowed_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins).add_plugins(GamePlugin {
+        seed: arg_value("--seed").and_then(|seed| seed.parse().ok()),
+    });
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        if let Some(path) = arg_value("--record") {
+            app.world.resource_mut::<ReplayRecorder>().save_to = Some(path.into());
+        }
+        if let Some(path) = arg_value("--replay") {
+            match Replay::read_from(path.as_ref()) {
+                // Loading goes straight into the round once it sees this.
+                Ok(replay) => {
+                    app.insert_resource(ReplayPlayback::new(replay));
+                }
+                Err(err) => error!("Could not load replay {path}: {err}"),
+            }
+        }
+    }
+
+    app
+}
+
+/// Reads `<name> <value>` from the command line. Always `None` on the web.
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args();
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+    }
+    None
+}
+
+/// Builds the full game on `MinimalPlugins`, with no window, renderer or
+/// audio device.
+///
+/// Every `App::update` advances time by exactly one gameplay tick, so a caller can
+/// step the simulation as fast as the machine allows. Pass a seed to replay
+/// the same rounds on every run. Nothing is saved to disk.
+pub fn headless_app(seed: Option<u64>) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        TransformPlugin,
+        HierarchyPlugin,
+        InputPlugin,
+    ))
+    .init_asset::<Image>()
+    .init_asset::<Font>()
+    .init_asset::<Mesh>()
+    .init_asset::<ColorMaterial>()
+    .init_asset::<AudioSource>()
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / TICK_RATE_HZ,
+    )))
+    .insert_resource(Storage::in_memory())
+    .add_plugins(GamePlugin { seed })
+    .add_systems(Last, discard_audio);
+    app
+}
+
+/// How many gameplay ticks run per second, whatever the frame rate.
+pub const TICK_RATE_HZ: f64 = 60.0;
+
+/// Every gameplay plugin, independent of how the app is presented.
+#[derive(Default)]
+pub struct GamePlugin {
+    /// Fixed RNG seed for every round, or `None` for a new seed each round.
+    pub seed: Option<u64>,
+}
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<AppState>()
+            .insert_resource(Time::<Fixed>::from_hz(TICK_RATE_HZ))
+            .configure_sets(
+                FixedUpdate,
+                (
+                    GameplaySet::Input,
+                    GameplaySet::Spawn,
+                    GameplaySet::Movement,
+                    GameplaySet::CollisionDetection,
+                    GameplaySet::Damage,
+                    GameplaySet::CollisionResponse,
+                )
+                    .chain(),
+            )
+            .add_plugins(StoragePlugin)
+            .add_plugins(ActionsPlugin)
+            .add_plugins(GamepadPlugin)
+            .add_plugins(TouchPlugin)
+            .add_plugins(MousePlugin)
+            .add_plugins(RngPlugin { seed: self.seed })
+            .add_plugins(ReplayPlugin)
+            .add_plugins(IntroScreenPlugin)
+            .add_plugins(SceneLoaderPlugin)
+            .add_plugins(HudPlugin)
+            .add_plugins(HighscoresPlugin)
+            .add_plugins(ControlsPlugin)
+            .add_plugins(MovementPlugin)
+            .add_plugins(FighterPlugin)
+            .add_plugins(AliensPlugin)
+            .add_plugins(StagePlugin)
+            .add_plugins(BossPlugin)
+            .add_plugins(BulletPatternsPlugin)
+            .add_plugins(PickupsPlugin)
+            .add_plugins(BombsPlugin)
+            .add_plugins(ExplosionsPlugin)
+            .add_plugins(PausePlugin)
+            .add_plugins(CollisionDetectionPlugin)
+            .add_plugins(HealthPlugin);
+    }
+}
+
+/// Without an audio backend nothing ever finishes playing, so drop sound
+/// entities as soon as they are spawned.
+fn discard_audio(mut commands: Commands, query: Query<Entity, With<Handle<AudioSource>>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
 }
 
 #[derive(States, Debug, Clone, Eq, PartialEq, Hash, Default)]
 pub enum AppState {
+    /// Waits for the stages to load before showing the title screen.
     #[default]
+    Loading,
     IntroScreen,
     Highscores,
+    Controls,
     Game,
 }
+
+/// Order of the gameplay systems within each `FixedUpdate` tick.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameplaySet {
+    Input,
+    Spawn,
+    Movement,
+    CollisionDetection,
+    /// Applies hits to `Health` and sends `Killed`.
+    Damage,
+    CollisionResponse,
+}
@@ -0,0 +1,126 @@
+//! Steering by following the mouse, so the game can be played one-handed.
+//!
+//! With `SteeringMode::FollowMouse` the fighter eases toward the cursor,
+//! the left button fires and the right button pauses. Outside a running
+//! round the mouse only works the menus' buttons. The steering goes through
+//! the move axis like any other device, so the fighter keeps its top speed
+//! and stays inside the playfield.
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::{Action, ActionSet, ActionState, InputDevice},
+    fighter::Player,
+    movement::Position,
+    pause::PauseState,
+    AppState,
+};
+
+/// Closer to the cursor than this the fighter slows down, so it settles
+/// under the cursor rather than overshooting it.
+const EASE_DISTANCE: f32 = 60.0;
+
+/// How the fighter is steered.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SteeringMode {
+    /// By the move actions or a stick.
+    #[default]
+    Direct,
+    /// Toward the mouse cursor.
+    FollowMouse,
+}
+
+impl SteeringMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SteeringMode::Direct => "DIRECT",
+            SteeringMode::FollowMouse => "FOLLOW MOUSE",
+        }
+    }
+
+    pub fn toggled(&self) -> Self {
+        match self {
+            SteeringMode::Direct => SteeringMode::FollowMouse,
+            SteeringMode::FollowMouse => SteeringMode::Direct,
+        }
+    }
+}
+
+/// The mouse button for an action while following the mouse, if it has one.
+pub fn mouse_label(action: Action) -> Option<&'static str> {
+    match action {
+        Action::Fire => Some("LEFT CLICK"),
+        Action::Pause => Some("RIGHT CLICK"),
+        _ => None,
+    }
+}
+
+pub struct MousePlugin;
+
+impl Plugin for MousePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SteeringMode>()
+            .add_systems(PreUpdate, read_mouse.in_set(ActionSet));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn read_mouse(
+    mode: Res<SteeringMode>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    player_query: Query<&Position, With<Player>>,
+    app_state: Res<State<AppState>>,
+    pause_state: Res<State<PauseState>>,
+    mut state: ResMut<ActionState>,
+    mut device: ResMut<InputDevice>,
+    mut menu_click: Local<bool>,
+) {
+    if *mode != SteeringMode::FollowMouse {
+        return;
+    }
+
+    if mouse_input.get_just_pressed().next().is_some() {
+        device.set_if_neq(InputDevice::Mouse);
+    }
+    if *app_state.get() != AppState::Game {
+        return;
+    }
+    if mouse_input.pressed(MouseButton::Right) {
+        state.press(Action::Pause);
+    }
+
+    // The pause menu is clicked, not steered. A click on it mustn't fire
+    // either, even once the button it pressed has resumed the round.
+    if *pause_state.get() == PauseState::Paused {
+        *menu_click |= mouse_input.just_pressed(MouseButton::Left);
+        return;
+    }
+    if !mouse_input.pressed(MouseButton::Left) {
+        *menu_click = false;
+    } else if !*menu_click {
+        state.press(Action::Fire);
+    }
+
+    let Ok(position) = player_query.get_single() else {
+        return;
+    };
+    let Some(cursor) = window_query
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    let Some(target) = camera.viewport_to_world_2d(camera_transform, cursor) else {
+        return;
+    };
+
+    let offset = target - position.value.truncate();
+    state.push_axis((offset / EASE_DISTANCE).clamp_length_max(1.0));
+}
@@ -1,15 +1,32 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, transform::TransformSystem};
 
-use crate::AppState;
+use crate::{
+    scene::{PlayfieldBounds, Size},
+    AppState, GameplaySet,
+};
 
+/// Where an entity is in the simulation.
+///
+/// Gameplay only ever moves `Position`, once per fixed tick. `Transform` is
+/// derived from it every frame by interpolating between the last two ticks.
 #[derive(Component, Debug)]
 pub struct Position {
     pub value: Vec3,
+    pub previous: Vec3,
 }
 
 impl Position {
     pub fn new(value: Vec3) -> Self {
-        Self { value }
+        Self {
+            value,
+            previous: value,
+        }
+    }
+
+    /// Moves without interpolating across the jump.
+    pub fn teleport(&mut self, value: Vec3) {
+        self.value = value;
+        self.previous = value;
     }
 }
 
@@ -28,35 +45,55 @@ pub struct MovementPlugin;
 
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, update_position.run_if(in_state(AppState::Game)));
+        app.add_systems(FixedFirst, store_previous_positions)
+            .add_systems(
+                FixedUpdate,
+                update_position
+                    .in_set(GameplaySet::Movement)
+                    .run_if(in_state(AppState::Game)),
+            )
+            .add_systems(
+                PostUpdate,
+                interpolate_transforms.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn store_previous_positions(mut query: Query<&mut Position>) {
+    for mut position in query.iter_mut() {
+        position.previous = position.value;
     }
 }
 
-fn update_position(
+pub(crate) fn update_position(
     time: Res<Time>,
-    window: Query<&Window>,
-    mut query: Query<(
-        &Velocity,
-        &mut Position,
-        &mut Transform,
-        Entity,
-        &Handle<Image>,
-    )>,
+    bounds: Res<PlayfieldBounds>,
+    mut query: Query<(&Velocity, &mut Position, Entity, &Size)>,
     mut commands: Commands,
-    assets: Res<Assets<Image>>,
 ) {
-    let window = window.single();
-    for (velocity, mut position, mut transform, entity, image_handle) in query.iter_mut() {
-        transform.translation += velocity.value * time.delta_seconds();
-        position.value = transform.translation;
-        let image_size = assets.get(image_handle).unwrap().size_f32();
-
-        if position.value.x > (window.width() / 2.0) + (image_size.x / 2.0)
-            || position.value.x < (-window.width() / 2.0) - (image_size.x / 2.0)
-            || position.value.y > (window.height() / 2.0) + (image_size.y / 2.0)
-            || position.value.y < (-window.height() / 2.0) - (image_size.y / 2.0)
+    for (velocity, mut position, entity, size) in query.iter_mut() {
+        position.value += velocity.value * time.delta_seconds();
+
+        // Entities still heading onto the playfield, like a formation
+        // queued up past the right edge, are left alone.
+        let limit = (Vec2::new(bounds.width, bounds.height) + size.value.truncate()) / 2.0;
+        let leaving = |position: f32, velocity: f32, limit: f32| {
+            (position > limit && velocity >= 0.0) || (position < -limit && velocity <= 0.0)
+        };
+        if leaving(position.value.x, velocity.value.x, limit.x)
+            || leaving(position.value.y, velocity.value.y, limit.y)
         {
             commands.entity(entity).despawn_recursive();
         }
     }
 }
+
+fn interpolate_transforms(
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&Position, &mut Transform)>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    for (position, mut transform) in query.iter_mut() {
+        transform.translation = position.previous.lerp(position.value, alpha);
+    }
+}
@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState},
+    AppState,
+};
+
+/// Whether a game in progress is paused. Only meaningful in `AppState::Game`.
+///
+/// Pausing stops virtual time, so nothing in `FixedUpdate` runs and every
+/// timer holds its value until the game resumes.
+#[derive(States, Debug, Clone, Eq, PartialEq, Hash, Default)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum PauseMenuButton {
+    Resume,
+    Restart,
+    QuitToTitle,
+}
+
+impl PauseMenuButton {
+    const ALL: [PauseMenuButton; 3] = [
+        PauseMenuButton::Resume,
+        PauseMenuButton::Restart,
+        PauseMenuButton::QuitToTitle,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            PauseMenuButton::Resume => "RESUME",
+            PauseMenuButton::Restart => "RESTART",
+            PauseMenuButton::QuitToTitle => "QUIT TO TITLE",
+        }
+    }
+}
+
+/// Index into `PauseMenuButton::ALL` of the highlighted entry.
+#[derive(Resource, Debug, Default)]
+struct PauseMenuSelection(usize);
+
+#[derive(Component, Debug)]
+struct PauseMenu;
+
+const IDLE_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.0);
+const SELECTED_COLOR: Color = Color::rgba(0.35, 0.75, 0.35, 0.6);
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<PauseState>()
+            .init_resource::<PauseMenuSelection>()
+            .add_systems(
+                Update,
+                (
+                    toggle_pause.run_if(in_state(AppState::Game)),
+                    (navigate_menu, highlight_selection)
+                        .chain()
+                        .run_if(in_state(PauseState::Paused)),
+                ),
+            )
+            .add_systems(OnEnter(PauseState::Paused), (pause_time, setup_menu))
+            .add_systems(OnExit(PauseState::Paused), (resume_time, despawn_menu))
+            .add_systems(OnExit(AppState::Game), unpause);
+    }
+}
+
+fn toggle_pause(
+    actions: Res<ActionState>,
+    pause_state: Res<State<PauseState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        next_pause_state.set(match pause_state.get() {
+            PauseState::Running => PauseState::Paused,
+            PauseState::Paused => PauseState::Running,
+        });
+    }
+}
+
+fn unpause(mut next_pause_state: ResMut<NextState<PauseState>>) {
+    next_pause_state.set(PauseState::Running);
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn setup_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut selection: ResMut<PauseMenuSelection>,
+) {
+    selection.0 = 0;
+    let font = asset_server.load("fonts/Orbitron-VariableFont_wght.ttf");
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    position_type: PositionType::Absolute,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            PauseMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    "PAUSED",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 50.0,
+                        ..default()
+                    },
+                ),
+                style: Style {
+                    margin: UiRect::bottom(Val::Px(30.0)),
+                    ..default()
+                },
+                ..default()
+            });
+
+            for button in PauseMenuButton::ALL {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(360.0),
+                                padding: UiRect::all(Val::Px(10.0)),
+                                margin: UiRect::all(Val::Px(5.0)),
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            background_color: IDLE_COLOR.into(),
+                            ..default()
+                        },
+                        button,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            button.label(),
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 32.0,
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn navigate_menu(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    buttons: Query<(&Interaction, &PauseMenuButton), Changed<Interaction>>,
+    mut selection: ResMut<PauseMenuSelection>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    let count = PauseMenuButton::ALL.len();
+    if actions.just_pressed(Action::MoveUp) {
+        selection.0 = (selection.0 + count - 1) % count;
+    }
+    if actions.just_pressed(Action::MoveDown) {
+        selection.0 = (selection.0 + 1) % count;
+    }
+
+    let mut chosen = actions
+        .just_pressed(Action::Confirm)
+        .then(|| PauseMenuButton::ALL[selection.0]);
+
+    for (interaction, button) in buttons.iter() {
+        let index = PauseMenuButton::ALL
+            .iter()
+            .position(|b| b == button)
+            .unwrap();
+        match interaction {
+            Interaction::Hovered => selection.0 = index,
+            Interaction::Pressed => chosen = Some(*button),
+            Interaction::None => {}
+        }
+    }
+
+    match chosen {
+        Some(PauseMenuButton::Resume) => next_pause_state.set(PauseState::Running),
+        Some(PauseMenuButton::Restart) => {
+            next_pause_state.set(PauseState::Running);
+            commands.add(restart_round);
+        }
+        Some(PauseMenuButton::QuitToTitle) => next_state.set(AppState::IntroScreen),
+        None => {}
+    }
+}
+
+/// Present while a round is being restarted, for the exit systems that
+/// should only run when the player really leaves the round.
+#[derive(Resource, Debug)]
+pub(crate) struct RestartingRound;
+
+/// `AppState::Game` cannot transition to itself, so run its exit and enter
+/// schedules directly to tear the round down and start a fresh one.
+fn restart_round(world: &mut World) {
+    world.insert_resource(RestartingRound);
+    world.run_schedule(OnExit(AppState::Game));
+    world.run_schedule(OnEnter(AppState::Game));
+    world.remove_resource::<RestartingRound>();
+}
+
+fn highlight_selection(
+    selection: Res<PauseMenuSelection>,
+    mut buttons: Query<(&PauseMenuButton, &mut BackgroundColor)>,
+) {
+    for (button, mut background) in buttons.iter_mut() {
+        let selected = PauseMenuButton::ALL[selection.0] == *button;
+        *background = if selected { SELECTED_COLOR } else { IDLE_COLOR }.into();
+    }
+}
+
+fn despawn_menu(mut commands: Commands, query: Query<Entity, With<PauseMenu>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
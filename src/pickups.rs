@@ -0,0 +1,235 @@
+use bevy::prelude::*;
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    bombs::{Bombs, MAX_BOMBS},
+    collisions::{Collider, CollisionEvent, CollisionLayers, CollisionPhase, Layer},
+    fighter::{handle_player_death, Lives, Player},
+    health::Shielded,
+    hud::CurrentScore,
+    movement::{Position, Velocity},
+    scene::Size,
+    weapons::{Weapon, WeaponKind},
+    AppState, GameplaySet,
+};
+
+/// Chance that a killed alien drops anything at all.
+const DROP_CHANCE: f64 = 0.12;
+/// What a drop turns out to be, relative to the others.
+const DROP_TABLE: [(PickupKind, u32); 9] = [
+    (PickupKind::RapidFire, 30),
+    (PickupKind::Weapon(WeaponKind::Blaster), 8),
+    (PickupKind::Weapon(WeaponKind::Spread), 8),
+    (PickupKind::Weapon(WeaponKind::TwinLaser), 6),
+    (PickupKind::Weapon(WeaponKind::RearGun), 5),
+    (PickupKind::Shield, 15),
+    (PickupKind::Bomb, 6),
+    (PickupKind::ExtraLife, 5),
+    (PickupKind::ScoreMedal, 25),
+];
+const PICKUP_SIZE: f32 = 22.0;
+const PICKUP_SPEED: f32 = 80.0;
+const RAPID_FIRE_SECONDS: f32 = 10.0;
+const SHIELD_SECONDS: f32 = 8.0;
+const MEDAL_SCORE: u32 = 10;
+const SHIELD_TINT: Color = Color::rgb(0.6, 0.9, 1.0);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickupKind {
+    RapidFire,
+    /// Levels up the weapon if it is the one already fitted, or swaps it in.
+    Weapon(WeaponKind),
+    Shield,
+    /// One more smart bomb, up to the most that can be carried.
+    Bomb,
+    ExtraLife,
+    ScoreMedal,
+}
+
+impl PickupKind {
+    fn label(&self) -> &'static str {
+        match self {
+            PickupKind::RapidFire => "R",
+            PickupKind::Weapon(WeaponKind::Blaster) => "P",
+            PickupKind::Weapon(WeaponKind::Spread) => "S",
+            PickupKind::Weapon(WeaponKind::TwinLaser) => "L",
+            PickupKind::Weapon(WeaponKind::RearGun) => "B",
+            PickupKind::Shield => "D",
+            PickupKind::Bomb => "*",
+            PickupKind::ExtraLife => "1UP",
+            PickupKind::ScoreMedal => "$",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            PickupKind::RapidFire => Color::rgb(1.0, 0.6, 0.1),
+            PickupKind::Weapon(_) => Color::rgb(0.3, 0.9, 0.3),
+            PickupKind::Shield => Color::rgb(0.3, 0.6, 1.0),
+            PickupKind::Bomb => Color::rgb(0.8, 0.4, 1.0),
+            PickupKind::ExtraLife => Color::rgb(1.0, 0.3, 0.6),
+            PickupKind::ScoreMedal => Color::rgb(1.0, 0.85, 0.2),
+        }
+    }
+}
+
+/// Put on with [`Shielded`], keeping the colour the ship had before.
+#[derive(Component, Debug)]
+struct ShieldTint {
+    restore: Color,
+}
+
+/// Timed effects on the player, in seconds left. Lost with the ship.
+#[derive(Component, Debug, Default, Clone)]
+pub struct PowerUps {
+    pub rapid_fire: f32,
+    pub shield: f32,
+}
+
+impl PowerUps {
+    /// The effects still running, for the HUD.
+    pub fn active(&self) -> impl Iterator<Item = (&'static str, f32)> {
+        [("RAPID", self.rapid_fire), ("SHIELD", self.shield)]
+            .into_iter()
+            .filter(|(_, remaining)| *remaining > 0.0)
+    }
+}
+
+pub struct PickupsPlugin;
+
+impl Plugin for PickupsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                tick_power_ups.in_set(GameplaySet::Movement),
+                // An extra life picked up on the tick the player dies still counts.
+                collect_pickups
+                    .in_set(GameplaySet::CollisionResponse)
+                    .before(handle_player_death),
+            )
+                .run_if(in_state(AppState::Game)),
+        )
+        .add_systems(OnExit(AppState::Game), despawn_pickups);
+    }
+}
+
+/// Rolls the drop table for a kill at `position`.
+pub fn roll_drop(
+    commands: &mut Commands,
+    rng: &mut ChaCha8Rng,
+    asset_server: &AssetServer,
+    position: Vec3,
+) {
+    if !rng.gen_bool(DROP_CHANCE) {
+        return;
+    }
+    let total: u32 = DROP_TABLE.iter().map(|(_, weight)| weight).sum();
+    let mut roll = rng.gen_range(0..total);
+    let Some(&(kind, _)) = DROP_TABLE.iter().find(|(_, weight)| {
+        let found = roll < *weight;
+        roll = roll.saturating_sub(*weight);
+        found
+    }) else {
+        return;
+    };
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Orbitron-VariableFont_wght.ttf"),
+        font_size: 14.0,
+        color: Color::BLACK,
+    };
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: kind.color(),
+                    custom_size: Some(Vec2::splat(PICKUP_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(position),
+                ..default()
+            },
+            kind,
+            Position::new(position),
+            Velocity::new(Vec3::new(-PICKUP_SPEED, 0.0, 0.0)),
+            Size::new((PICKUP_SIZE as u32, PICKUP_SIZE as u32)),
+            Collider::circle(PICKUP_SIZE / 2.0),
+            CollisionLayers::new(Layer::PICKUP, Layer::PLAYER),
+        ))
+        .with_children(|parent| {
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(kind.label(), text_style),
+                transform: Transform::from_xyz(0.0, 0.0, 1.0),
+                ..default()
+            });
+        });
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    pickup_query: Query<&PickupKind>,
+    mut player_query: Query<(&mut PowerUps, &mut Weapon), With<Player>>,
+    mut lives: ResMut<Lives>,
+    mut bombs: ResMut<Bombs>,
+    mut score: ResMut<CurrentScore>,
+) {
+    for event in collision_events.read() {
+        if event.phase != CollisionPhase::Enter {
+            continue;
+        }
+        for (entity, other) in event.pairs() {
+            let (Ok(kind), Ok((mut power_ups, mut weapon))) =
+                (pickup_query.get(entity), player_query.get_mut(other))
+            else {
+                continue;
+            };
+            match kind {
+                PickupKind::RapidFire => power_ups.rapid_fire = RAPID_FIRE_SECONDS,
+                PickupKind::Weapon(weapon_kind) => *weapon = weapon.picked_up(*weapon_kind),
+                PickupKind::Shield => power_ups.shield = SHIELD_SECONDS,
+                PickupKind::Bomb => bombs.remaining = (bombs.remaining + 1).min(MAX_BOMBS),
+                PickupKind::ExtraLife => lives.remaining += 1,
+                PickupKind::ScoreMedal => score.value += MEDAL_SCORE,
+            }
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn tick_power_ups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut PowerUps, &mut Sprite, Option<&ShieldTint>), With<Player>>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut power_ups, mut sprite, shield_tint) in query.iter_mut() {
+        power_ups.rapid_fire = (power_ups.rapid_fire - delta).max(0.0);
+        power_ups.shield = (power_ups.shield - delta).max(0.0);
+
+        match shield_tint {
+            None if power_ups.shield > 0.0 => {
+                commands.entity(entity).insert((
+                    Shielded,
+                    ShieldTint {
+                        restore: sprite.color,
+                    },
+                ));
+                sprite.color = SHIELD_TINT;
+            }
+            Some(tint) if power_ups.shield <= 0.0 => {
+                commands.entity(entity).remove::<(Shielded, ShieldTint)>();
+                sprite.color = tint.restore;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn despawn_pickups(mut commands: Commands, query: Query<Entity, With<PickupKind>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
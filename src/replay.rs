@@ -0,0 +1,273 @@
+use std::{error::Error, fmt};
+
+use bevy::prelude::*;
+
+use crate::{
+    fighter::PlayerInput,
+    pause::RestartingRound,
+    rng::{start_round, GameRng},
+    scene::PlayfieldBounds,
+    AppState, GameplaySet,
+};
+
+const MAGIC: &[u8; 4] = b"ASSR";
+/// Older versions drew from a different random generator and didn't record
+/// the playfield size, so their rounds can't be reproduced.
+const VERSION: u8 = 3;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 8 + 4 + 4 + 4;
+const RECORD_LEN: usize = 4;
+
+/// One round of player input, tick by tick, with the seed and playfield
+/// size it was played with.
+///
+/// Feeding the inputs back through the same seed and playfield reproduces
+/// the round exactly, since the simulation runs on a fixed timestep.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub bounds: PlayfieldBounds,
+    pub inputs: Vec<PlayerInput>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    NotAReplay,
+    UnsupportedVersion(u8),
+    Truncated,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::NotAReplay => write!(f, "not a replay file"),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "unsupported replay version {version}")
+            }
+            ReplayError::Truncated => write!(f, "replay file is truncated"),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+impl Replay {
+    /// Header (magic, version, seed, playfield width and height, tick
+    /// count), then the inputs as
+    /// run-length encoded `(button bits, steering x, steering y, run length)`
+    /// records.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.inputs.len() / 8);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.bounds.width.to_le_bytes());
+        bytes.extend_from_slice(&self.bounds.height.to_le_bytes());
+        bytes.extend_from_slice(&(self.inputs.len() as u32).to_le_bytes());
+
+        let mut inputs = self.inputs.iter().peekable();
+        while let Some(input) = inputs.next() {
+            let mut run: u8 = 1;
+            while run < u8::MAX && inputs.next_if_eq(&input).is_some() {
+                run += 1;
+            }
+            bytes.extend_from_slice(&[
+                input.button_bits(),
+                input.move_x as u8,
+                input.move_y as u8,
+                run,
+            ]);
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, ReplayError> {
+        if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(ReplayError::NotAReplay);
+        }
+        if bytes.len() < HEADER_LEN {
+            return Err(ReplayError::Truncated);
+        }
+        let version = bytes[4];
+        if version != VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let seed = u64::from_le_bytes(bytes[5..13].try_into().unwrap());
+        let bounds = PlayfieldBounds {
+            width: f32::from_le_bytes(bytes[13..17].try_into().unwrap()),
+            height: f32::from_le_bytes(bytes[17..21].try_into().unwrap()),
+        };
+        let ticks = u32::from_le_bytes(bytes[21..25].try_into().unwrap()) as usize;
+
+        // Check the tick count against what the records can hold before
+        // reserving room for it, so a corrupt header can't ask for gigabytes.
+        let records = &bytes[HEADER_LEN..];
+        if ticks > records.len() / RECORD_LEN * u8::MAX as usize {
+            return Err(ReplayError::Truncated);
+        }
+
+        let mut inputs = Vec::with_capacity(ticks);
+        for run in records.chunks(RECORD_LEN) {
+            let &[bits, move_x, move_y, length] = run else {
+                return Err(ReplayError::Truncated);
+            };
+            let input = PlayerInput::from_parts(bits, move_x as i8, move_y as i8);
+            inputs.extend(std::iter::repeat(input).take(length as usize));
+        }
+        if inputs.len() != ticks {
+            return Err(ReplayError::Truncated);
+        }
+
+        Ok(Self {
+            seed,
+            bounds,
+            inputs,
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_from(path: &std::path::Path) -> std::io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::decode(&bytes)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_to(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, self.encode())
+    }
+}
+
+/// Replaces the keyboard with a recorded replay for the next round.
+///
+/// Restarting the round from the pause menu plays the replay again from
+/// the start.
+#[derive(Resource, Debug)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    tick: usize,
+    /// The fixed seed and playfield to put back when playback ends, saved
+    /// when it begins.
+    previous: Option<(Option<u64>, PlayfieldBounds)>,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            tick: 0,
+            previous: None,
+        }
+    }
+}
+
+/// Records every round's input. The last finished round is kept in
+/// `last`, and also written to `save_to` when that is set.
+#[derive(Resource, Debug, Default)]
+pub struct ReplayRecorder {
+    inputs: Vec<PlayerInput>,
+    bounds: PlayfieldBounds,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub save_to: Option<std::path::PathBuf>,
+    pub last: Option<Replay>,
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayRecorder>()
+            .add_systems(
+                OnEnter(AppState::Game),
+                (
+                    begin_playback
+                        .before(start_round)
+                        .run_if(resource_exists::<ReplayPlayback>),
+                    begin_recording.after(begin_playback),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                play_back_input
+                    .in_set(GameplaySet::Input)
+                    .run_if(in_state(AppState::Game).and_then(resource_exists::<ReplayPlayback>)),
+            )
+            .add_systems(
+                FixedPostUpdate,
+                record_input.run_if(in_state(AppState::Game)),
+            )
+            .add_systems(
+                OnExit(AppState::Game),
+                (
+                    finish_recording,
+                    end_playback.run_if(resource_exists::<ReplayPlayback>),
+                )
+                    .run_if(not(resource_exists::<RestartingRound>)),
+            );
+    }
+}
+
+/// The playfield is swapped for the recorded one until playback ends, so
+/// spawns, clamps and despawns match the recording whatever the window size.
+fn begin_playback(
+    mut playback: ResMut<ReplayPlayback>,
+    mut rng: ResMut<GameRng>,
+    mut bounds: ResMut<PlayfieldBounds>,
+) {
+    playback.tick = 0;
+    if playback.previous.is_none() {
+        playback.previous = Some((rng.fixed_seed(), *bounds));
+    }
+    rng.set_fixed_seed(Some(playback.replay.seed));
+    *bounds = playback.replay.bounds;
+}
+
+fn play_back_input(mut playback: ResMut<ReplayPlayback>, mut player_input: ResMut<PlayerInput>) {
+    // Once the recording runs out the player simply stops touching the controls.
+    *player_input = playback
+        .replay
+        .inputs
+        .get(playback.tick)
+        .copied()
+        .unwrap_or_default();
+    playback.tick += 1;
+}
+
+fn end_playback(
+    mut commands: Commands,
+    playback: Res<ReplayPlayback>,
+    mut rng: ResMut<GameRng>,
+    mut bounds: ResMut<PlayfieldBounds>,
+) {
+    if let Some((fixed_seed, previous_bounds)) = playback.previous {
+        rng.set_fixed_seed(fixed_seed);
+        *bounds = previous_bounds;
+    }
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+fn begin_recording(mut recorder: ResMut<ReplayRecorder>, bounds: Res<PlayfieldBounds>) {
+    recorder.inputs.clear();
+    recorder.bounds = *bounds;
+}
+
+fn record_input(mut recorder: ResMut<ReplayRecorder>, player_input: Res<PlayerInput>) {
+    recorder.inputs.push(*player_input);
+}
+
+fn finish_recording(mut recorder: ResMut<ReplayRecorder>, rng: Res<GameRng>) {
+    let replay = Replay {
+        seed: rng.seed(),
+        bounds: recorder.bounds,
+        inputs: std::mem::take(&mut recorder.inputs),
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = &recorder.save_to {
+        match replay.write_to(path) {
+            Ok(()) => info!("Saved replay to {}", path.display()),
+            Err(err) => error!("Could not save replay to {}: {err}", path.display()),
+        }
+    }
+
+    recorder.last = Some(replay);
+}
@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::AppState;
+
+/// Offset mixed into the seed so the cosmetic stream never mirrors gameplay.
+const COSMETIC_STREAM: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Every random number the game uses comes from here.
+///
+/// Gameplay (alien waves, fire timing) and cosmetics (explosions, stars) use
+/// separate streams, so a change to a visual effect never changes what the
+/// aliens do. Both streams restart from the round's seed when a game starts.
+///
+/// The generator is ChaCha8, which rand promises to keep producing the same
+/// numbers for a seed. `StdRng` may change between rand versions, which
+/// would break every saved replay.
+#[derive(Resource, Debug)]
+pub struct GameRng {
+    fixed_seed: Option<u64>,
+    seed: u64,
+    pub gameplay: ChaCha8Rng,
+    pub cosmetic: ChaCha8Rng,
+}
+
+impl GameRng {
+    /// With `Some(seed)` every round replays the same waves; with `None`
+    /// each round picks a fresh seed.
+    pub fn new(fixed_seed: Option<u64>) -> Self {
+        let seed = fixed_seed.unwrap_or_else(rand::random);
+        Self {
+            fixed_seed,
+            seed,
+            gameplay: ChaCha8Rng::seed_from_u64(seed),
+            cosmetic: ChaCha8Rng::seed_from_u64(seed ^ COSMETIC_STREAM),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn fixed_seed(&self) -> Option<u64> {
+        self.fixed_seed
+    }
+
+    /// Takes effect from the next round.
+    pub fn set_fixed_seed(&mut self, fixed_seed: Option<u64>) {
+        self.fixed_seed = fixed_seed;
+    }
+
+    fn reseed(&mut self) {
+        *self = Self::new(self.fixed_seed);
+    }
+}
+
+pub struct RngPlugin {
+    pub seed: Option<u64>,
+}
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GameRng::new(self.seed))
+            .add_systems(OnEnter(AppState::Game), start_round);
+    }
+}
+
+pub(crate) fn start_round(mut rng: ResMut<GameRng>) {
+    rng.reseed();
+    info!("Starting round with seed {}", rng.seed());
+}
@@ -2,15 +2,18 @@ use bevy::{
     audio::Volume,
     prelude::*,
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
-    window::EnabledButtons,
+    window::{EnabledButtons, PrimaryWindow, WindowResolution},
 };
+use rand::Rng;
 
 use crate::{
     aliens::{Alien, AlienBullet},
-    fighter::{GameOverCountdown, PlayerBullet},
-    hud::CurrentScore,
+    fighter::{GameOver, GameOverCountdown, Player, PlayerBullet, RespawnCountdown},
     movement::{Position, Velocity},
-    AppState,
+    replay::ReplayPlayback,
+    rng::GameRng,
+    weapons::Projectile,
+    AppState, GameplaySet,
 };
 
 const BACKGROUND_SCROLL_SPEED: f32 = 100.0;
@@ -19,7 +22,8 @@ const MAX_STARS: u32 = 500;
 #[derive(Resource, Debug, Default)]
 pub struct SceneAssets {
     pub player: ImageBox,
-    pub player_bullet: ImageBox,
+    /// One for each of `Projectile::ALL`, in the same order.
+    pub projectiles: Vec<ImageBox>,
     // pub background: ImageBox,
     pub alien: ImageBox,
     pub alien_bullet: ImageBox,
@@ -40,6 +44,22 @@ pub struct ImageBox {
     pub dimensions: (u32, u32),
 }
 
+impl SceneAssets {
+    pub fn projectile(&self, projectile: Projectile) -> &ImageBox {
+        let index = Projectile::ALL
+            .iter()
+            .position(|p| *p == projectile)
+            .unwrap();
+        &self.projectiles[index]
+    }
+}
+
+impl ImageBox {
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.dimensions.0 as f32, self.dimensions.1 as f32)
+    }
+}
+
 #[derive(Component, Debug)]
 pub struct Size {
     pub value: Vec3,
@@ -58,17 +78,47 @@ impl Size {
 #[derive(Component, Debug)]
 pub struct Star;
 
+/// The area the game is played in, centred on the origin.
+///
+/// Gameplay systems read this instead of the `Window` so the simulation can
+/// run headless. When a primary window exists its size is copied in here,
+/// except while a replay plays back in the playfield it was recorded in.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct PlayfieldBounds {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for PlayfieldBounds {
+    fn default() -> Self {
+        let resolution = WindowResolution::default();
+        Self {
+            width: resolution.width(),
+            height: resolution.height(),
+        }
+    }
+}
+
 pub struct SceneLoaderPlugin;
 
 impl Plugin for SceneLoaderPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<SceneAssets>()
             .init_resource::<SceneSounds>()
+            .init_resource::<PlayfieldBounds>()
+            .add_systems(PreStartup, sync_playfield_bounds)
+            .add_systems(
+                PreUpdate,
+                sync_playfield_bounds.run_if(not(resource_exists::<ReplayPlayback>)),
+            )
             .add_systems(Startup, (load_assets, load_background, load_stars, music))
-            .add_systems(Update, (scroll_background, handle_stars))
+            .add_systems(Update, scroll_background)
             .add_systems(
-                Update,
-                game_over_countdown_timer.run_if(in_state(AppState::Game)),
+                FixedUpdate,
+                (
+                    handle_stars.in_set(GameplaySet::Movement),
+                    game_over_countdown_timer.run_if(in_state(AppState::Game)),
+                ),
             )
             .add_systems(OnExit(AppState::Game), despawn_scene);
     }
@@ -85,10 +135,13 @@ fn load_assets(
         image: asset_server.load("craft.png"),
         dimensions: (75, 33),
     };
-    let player_bullet = ImageBox {
-        image: asset_server.load("playerBullet.png"),
-        dimensions: (26, 9),
-    };
+    let projectiles = Projectile::ALL
+        .iter()
+        .map(|projectile| ImageBox {
+            image: asset_server.load(projectile.image_path()),
+            dimensions: projectile.dimensions(),
+        })
+        .collect();
     // let background = ImageBox {
     //     image: asset_server.load("background_space.png"),
     //     dimensions: (1104, 1021),
@@ -109,7 +162,7 @@ fn load_assets(
 
     *scene_assets = SceneAssets {
         player,
-        player_bullet,
+        projectiles,
         // background,
         alien,
         alien_bullet,
@@ -129,27 +182,41 @@ fn load_assets(
     }
 }
 
+fn sync_playfield_bounds(
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut bounds: ResMut<PlayfieldBounds>,
+) {
+    if let Ok(window) = window.get_single() {
+        if bounds.width != window.width() || bounds.height != window.height() {
+            bounds.width = window.width();
+            bounds.height = window.height();
+        }
+    }
+}
+
 fn load_background(
     mut commands: Commands,
     mut window: Query<&mut Window>,
     // scene_assets: Res<SceneAssets>,
     asset_server: Res<AssetServer>,
+    bounds: Res<PlayfieldBounds>,
 ) {
-    let mut window = window.single_mut();
-    window.enabled_buttons = EnabledButtons {
-        maximize: false,
-        ..default()
-    };
-    window.resizable = false;
+    if let Ok(mut window) = window.get_single_mut() {
+        window.enabled_buttons = EnabledButtons {
+            maximize: false,
+            ..default()
+        };
+        window.resizable = false;
+    }
     for i in 0..2 {
         commands.spawn((
             SpriteBundle {
                 sprite: Sprite {
-                    custom_size: Some(Vec2::new(window.width(), window.height())),
+                    custom_size: Some(Vec2::new(bounds.width, bounds.height)),
                     ..default()
                 },
                 texture: asset_server.load("background_space.png"),
-                transform: Transform::from_xyz(window.width() * i as f32, 0.0, -1.0),
+                transform: Transform::from_xyz(bounds.width * i as f32, 0.0, -1.0),
                 ..default()
             },
             Background,
@@ -159,22 +226,20 @@ fn load_background(
 
 fn load_stars(
     mut commands: Commands,
-    mut window: Query<&mut Window>,
+    bounds: Res<PlayfieldBounds>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut rng: ResMut<GameRng>,
 ) {
-    let window = window.single();
     for i in 0..MAX_STARS {
-        let velocity = rand::random::<f32>() * 8.0;
+        let velocity = rng.cosmetic.gen::<f32>() * 8.0;
         let color = 30 * velocity as u8;
         let velocity = Velocity::new(Vec3::new(-velocity, 0.0, 0.0));
-        let position = Position {
-            value: Vec3::new(
-                rand::random::<f32>() * window.width() - window.width() / 2.0,
-                rand::random::<f32>() * window.height() - window.height() / 2.0,
-                0.0,
-            ),
-        };
+        let position = Position::new(Vec3::new(
+            rng.cosmetic.gen::<f32>() * bounds.width - bounds.width / 2.0,
+            rng.cosmetic.gen::<f32>() * bounds.height - bounds.height / 2.0,
+            0.0,
+        ));
         commands.spawn((
             MaterialMesh2dBundle {
                 mesh: Mesh2dHandle(meshes.add(Rectangle::new(1.0, 3.0))),
@@ -191,44 +256,39 @@ fn load_stars(
 
 fn handle_stars(
     time: Res<Time>,
-    window: Query<&Window>,
-    mut query: Query<(&mut Position, &mut Transform, &Velocity), With<Star>>,
+    bounds: Res<PlayfieldBounds>,
+    mut query: Query<(&mut Position, &Velocity), With<Star>>,
 ) {
-    let window = window.single();
-
-    for (mut position, mut transform, velocity) in query.iter_mut() {
+    for (mut position, velocity) in query.iter_mut() {
         position.value += velocity.value * 60.0 * time.delta_seconds();
-        transform.translation += velocity.value * 60.0 * time.delta_seconds();
 
-        if position.value.x < -window.width() / 2.0 {
-            position.value.x += window.width();
-            transform.translation.x += window.width();
+        if position.value.x < -bounds.width / 2.0 {
+            let wrapped = position.value + Vec3::X * bounds.width;
+            position.teleport(wrapped);
         }
     }
 }
 
 fn scroll_background(
     time: Res<Time>,
-    window: Query<&Window>,
+    bounds: Res<PlayfieldBounds>,
     mut query: Query<(&mut Transform, &mut Sprite), With<Background>>,
 ) {
-    let window = window.single();
-
     let scroll_amount = BACKGROUND_SCROLL_SPEED * time.delta_seconds();
 
     for (mut transform, mut sprite) in query.iter_mut() {
         transform.translation.x -= scroll_amount;
 
-        if sprite.custom_size.unwrap().x != window.width() {
-            sprite.custom_size.unwrap().x = window.width();
-            sprite.custom_size.unwrap().y = window.height();
+        if sprite.custom_size.unwrap().x != bounds.width {
+            sprite.custom_size.unwrap().x = bounds.width;
+            sprite.custom_size.unwrap().y = bounds.height;
 
-            let bg_idx = (transform.translation.x / window.width()).round();
-            transform.translation.x = bg_idx * window.width();
+            let bg_idx = (transform.translation.x / bounds.width).round();
+            transform.translation.x = bg_idx * bounds.width;
         }
 
-        if transform.translation.x <= -window.width() {
-            transform.translation.x += 2.0 * window.width();
+        if transform.translation.x <= -bounds.width {
+            transform.translation.x += 2.0 * bounds.width;
         }
     }
 }
@@ -237,26 +297,40 @@ fn game_over_countdown_timer(
     mut commands: Commands,
     time: Res<Time>,
     mut next_state: ResMut<NextState<AppState>>,
+    mut game_over_events: EventWriter<GameOver>,
     mut query: Query<(Entity, &mut GameOverCountdown)>,
-    mut score: ResMut<CurrentScore>,
 ) {
     if let Ok((entity, mut timer)) = query.get_single_mut() {
         timer.value -= time.delta_seconds();
 
         if timer.value < 0.0 {
-            next_state.set(AppState::IntroScreen);
+            game_over_events.send(GameOver);
+            next_state.set(AppState::Highscores);
             commands.entity(entity).despawn_recursive();
-            score.reset();
         }
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn despawn_scene(
     mut commands: Commands,
+    player_query: Query<
+        Entity,
+        Or<(
+            With<Player>,
+            With<GameOverCountdown>,
+            With<RespawnCountdown>,
+        )>,
+    >,
     player_bullets_query: Query<Entity, With<PlayerBullet>>,
     aliens_query: Query<Entity, With<Alien>>,
     alien_bullets_query: Query<Entity, With<AlienBullet>>,
 ) {
+    // The round can end early from the pause menu, with the player still alive.
+    for entity in player_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
     for entity in player_bullets_query.iter() {
         commands.entity(entity).despawn_recursive();
     }
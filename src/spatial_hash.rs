@@ -0,0 +1,196 @@
+use bevy::{math::IVec2, prelude::*, utils::HashMap};
+
+/// Widens each box's cell range so rounding in `center ± size / 2` can never
+/// separate two boxes the narrow test considers overlapping.
+const CELL_PADDING: f32 = 0.01;
+
+/// A uniform grid broadphase for axis-aligned boxes.
+///
+/// Each box is bucketed into every cell it touches, and only boxes sharing a
+/// cell are tested against each other. The narrow test is the same strict
+/// AABB overlap the game has always used, so the pairs reported are exactly
+/// those a test of every pair would find.
+#[derive(Debug)]
+pub struct SpatialHash {
+    cell_size: f32,
+    boxes: Vec<(Vec2, Vec2)>,
+    cells: HashMap<IVec2, Vec<usize>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            boxes: Vec::new(),
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Removes every box. Buckets used since the last clear keep their
+    /// allocations for reuse; the rest are dropped, so cells left behind by
+    /// moving boxes don't pile up.
+    pub fn clear(&mut self) {
+        self.boxes.clear();
+        self.cells.retain(|_, bucket| {
+            let used = !bucket.is_empty();
+            bucket.clear();
+            used
+        });
+    }
+
+    /// Adds a box of the given full `size` and returns its index.
+    pub fn insert(&mut self, center: Vec2, size: Vec2) -> usize {
+        let index = self.boxes.len();
+        self.boxes.push((center, size));
+        let (min, max) = self.cell_range(index);
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(index);
+            }
+        }
+        index
+    }
+
+    /// Calls `f(a, b)` once for every overlapping pair, with `a < b`.
+    ///
+    /// Pairs are visited in insertion order of `a`, so results do not depend
+    /// on how the grid's buckets happen to be laid out.
+    pub fn for_each_overlapping_pair(&self, mut f: impl FnMut(usize, usize)) {
+        for a in 0..self.boxes.len() {
+            let (min_a, max_a) = self.cell_range(a);
+            for y in min_a.y..=max_a.y {
+                for x in min_a.x..=max_a.x {
+                    let cell = IVec2::new(x, y);
+                    for &b in &self.cells[&cell] {
+                        // Boxes spanning several cells meet in more than one
+                        // bucket. Only report them from the first they share.
+                        if b > a && self.cell_range(b).0.max(min_a) == cell && self.overlaps(a, b) {
+                            f(a, b);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn overlaps(&self, a: usize, b: usize) -> bool {
+        let (center_a, size_a) = self.boxes[a];
+        let (center_b, size_b) = self.boxes[b];
+        let distance = (center_a - center_b).abs();
+        distance.x < size_a.x / 2.0 + size_b.x / 2.0 && distance.y < size_a.y / 2.0 + size_b.y / 2.0
+    }
+
+    fn cell_range(&self, index: usize) -> (IVec2, IVec2) {
+        let (center, size) = self.boxes[index];
+        let half = size / 2.0 + CELL_PADDING;
+        (
+            ((center - half) / self.cell_size).floor().as_ivec2(),
+            ((center + half) / self.cell_size).floor().as_ivec2(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    const CELL_SIZE: f32 = 64.0;
+
+    fn pairwise_pairs(boxes: &[(Vec2, Vec2)]) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for (a, (center_a, size_a)) in boxes.iter().enumerate() {
+            for (b, (center_b, size_b)) in boxes.iter().enumerate().skip(a + 1) {
+                let distance = (*center_a - *center_b).abs();
+                if distance.x < size_a.x / 2.0 + size_b.x / 2.0
+                    && distance.y < size_a.y / 2.0 + size_b.y / 2.0
+                {
+                    pairs.push((a, b));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Every pair the grid reports, sorted but not deduplicated, so a pair
+    /// reported twice shows up as a mismatch.
+    fn grid_pairs(grid: &mut SpatialHash, boxes: &[(Vec2, Vec2)]) -> Vec<(usize, usize)> {
+        grid.clear();
+        for (center, size) in boxes {
+            grid.insert(*center, *size);
+        }
+        let mut pairs = Vec::new();
+        grid.for_each_overlapping_pair(|a, b| pairs.push((a, b)));
+        pairs.sort_unstable();
+        pairs
+    }
+
+    fn assert_matches_pairwise(boxes: &[(Vec2, Vec2)]) {
+        let mut grid = SpatialHash::new(CELL_SIZE);
+        assert_eq!(grid_pairs(&mut grid, boxes), pairwise_pairs(boxes));
+    }
+
+    #[test]
+    fn random_layouts_match_pairwise() {
+        let mut rng = ChaCha8Rng::seed_from_u64(9);
+        let mut grid = SpatialHash::new(CELL_SIZE);
+        for count in [0, 1, 2, 10, 100, 500] {
+            let boxes: Vec<_> = (0..count)
+                .map(|_| {
+                    let center =
+                        Vec2::new(rng.gen_range(-640.0..640.0), rng.gen_range(-360.0..360.0));
+                    let size = Vec2::new(rng.gen_range(1.0..200.0), rng.gen_range(1.0..200.0));
+                    (center, size)
+                })
+                .collect();
+            // Reusing one grid also checks that clear leaves nothing behind.
+            assert_eq!(grid_pairs(&mut grid, &boxes), pairwise_pairs(&boxes));
+        }
+
+        // A box flying across many cells must not leave a trail of buckets.
+        for frame in 0..1000 {
+            let center = Vec2::new(frame as f32 * 50.0, frame as f32 * -30.0);
+            grid_pairs(&mut grid, &[(center, Vec2::splat(10.0))]);
+            // At most four cells from this frame and four from the last,
+            // once the buckets from the big layouts above have been dropped.
+            if frame > 0 {
+                assert!(grid.cells.len() <= 8, "{} cells", grid.cells.len());
+            }
+        }
+    }
+
+    #[test]
+    fn boxes_straddling_cell_edges_match_pairwise() {
+        let mut boxes = Vec::new();
+        for i in -3..=3 {
+            let edge = i as f32 * CELL_SIZE;
+            // Centred on a cell edge, and just either side of it.
+            for offset in [0.0, -0.001, 0.001, -5.0, 5.0] {
+                boxes.push((Vec2::new(edge + offset, edge - offset), Vec2::splat(11.0)));
+            }
+            // Exactly touching across the edge, which is not an overlap.
+            boxes.push((Vec2::new(edge - 5.0, 0.0), Vec2::splat(10.0)));
+            boxes.push((Vec2::new(edge + 5.0, 0.0), Vec2::splat(10.0)));
+        }
+        // Larger than a cell, so spread over several.
+        boxes.push((Vec2::ZERO, Vec2::new(300.0, 20.0)));
+        boxes.push((
+            Vec2::new(CELL_SIZE, -CELL_SIZE),
+            Vec2::splat(CELL_SIZE * 2.0),
+        ));
+        assert_matches_pairwise(&boxes);
+    }
+
+    #[test]
+    fn touching_boxes_do_not_overlap() {
+        let boxes = [
+            (Vec2::new(-5.0, 0.0), Vec2::splat(10.0)),
+            (Vec2::new(5.0, 0.0), Vec2::splat(10.0)),
+            (Vec2::new(4.9, 9.9), Vec2::splat(10.0)),
+        ];
+        let mut grid = SpatialHash::new(CELL_SIZE);
+        assert_eq!(grid_pairs(&mut grid, &boxes), [(0, 2), (1, 2)]);
+    }
+}
@@ -0,0 +1,397 @@
+//! Stages are timelines of alien waves, loaded from `assets/stages`.
+//!
+//! A stage file is RON:
+//!
+//! ```ron
+//! (
+//!     name: "Stage 1",
+//!     events: [
+//!         (at: 0.0, action: Banner(text: "STAGE 1", seconds: 2.0)),
+//!         (at: 1.0, action: Spawn(kind: Weaver, count: 5, formation: Line(spacing: 90.0), y: 0.5)),
+//!         (at: 6.0, action: WaitUntilClear),
+//!     ],
+//! )
+//! ```
+//!
+//! `at` is in seconds on the stage clock, which stops while waiting for a
+//! wave to be cleared. Events must be listed in the order they happen.
+
+use std::fmt;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, LoadState},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use crate::{
+    alien_kinds::AlienKind,
+    aliens::{spawn_requested_aliens, Alien, SpawnAlien},
+    boss::SpawnBoss,
+    replay::ReplayPlayback,
+    scene::PlayfieldBounds,
+    AppState, GameplaySet,
+};
+
+/// Played in order, then from the top again.
+const STAGES: [&str; 2] = ["stages/1.stage.ron", "stages/2.stage.ron"];
+
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct Stage {
+    pub name: String,
+    pub events: Vec<StageEvent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StageEvent {
+    #[serde(default)]
+    pub at: f32,
+    pub action: StageAction,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum StageAction {
+    /// `y` is a fraction of the half height, from -1.0 (bottom) to 1.0 (top).
+    Spawn {
+        kind: AlienKind,
+        #[serde(default = "one")]
+        count: u32,
+        #[serde(default)]
+        formation: Formation,
+        #[serde(default)]
+        y: f32,
+    },
+    /// Stops the stage clock until every alien is gone.
+    WaitUntilClear,
+    Banner {
+        text: String,
+        seconds: f32,
+    },
+    /// Brings in the boss. The stage ends once it and every other alien
+    /// is destroyed.
+    Boss,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// How a group of aliens is laid out as it enters from the right.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum Formation {
+    #[default]
+    Single,
+    /// One behind the other.
+    Line { spacing: f32 },
+    /// Stacked vertically.
+    Column { spacing: f32 },
+    /// A leader with the rest trailing behind it above and below.
+    Vee { spacing: f32 },
+}
+
+impl Formation {
+    /// Where the `index`th alien starts, relative to the leader.
+    pub fn offset(&self, index: u32, count: u32) -> Vec2 {
+        match *self {
+            Formation::Single => Vec2::ZERO,
+            Formation::Line { spacing } => Vec2::new(index as f32 * spacing, 0.0),
+            Formation::Column { spacing } => {
+                Vec2::new(0.0, (index as f32 - (count - 1) as f32 / 2.0) * spacing)
+            }
+            Formation::Vee { spacing } => {
+                let row = index.div_ceil(2) as f32;
+                let side = if index % 2 == 0 { -1.0 } else { 1.0 };
+                Vec2::new(row * spacing, side * row * spacing)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StageLoaderError {
+    Io(std::io::Error),
+    Ron(ron::de::SpannedError),
+}
+
+impl fmt::Display for StageLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StageLoaderError::Io(err) => write!(f, "could not read stage: {err}"),
+            StageLoaderError::Ron(err) => write!(f, "could not parse stage: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for StageLoaderError {}
+
+#[derive(Default)]
+struct StageLoader;
+
+impl AssetLoader for StageLoader {
+    type Asset = Stage;
+    type Settings = ();
+    type Error = StageLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Stage, StageLoaderError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader
+                .read_to_end(&mut bytes)
+                .await
+                .map_err(StageLoaderError::Io)?;
+            ron::de::from_bytes(&bytes).map_err(StageLoaderError::Ron)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["stage.ron"]
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct StageLibrary {
+    pub stages: Vec<Handle<Stage>>,
+}
+
+/// Where the current round is in the stage list.
+#[derive(Resource, Debug, Default)]
+pub struct StageProgress {
+    pub stage: usize,
+    next_event: usize,
+    clock: f32,
+}
+
+#[derive(Component, Debug)]
+struct StageBanner {
+    remaining: f32,
+}
+
+/// Shown instead of the title screen when a stage could not be loaded.
+#[derive(Component, Debug)]
+struct LoadingError;
+
+pub struct StagePlugin;
+
+impl Plugin for StagePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Stage>()
+            .init_asset_loader::<StageLoader>()
+            .init_resource::<StageLibrary>()
+            .init_resource::<StageProgress>()
+            .add_systems(Startup, load_stages)
+            .add_systems(Update, finish_loading.run_if(in_state(AppState::Loading)))
+            .add_systems(OnEnter(AppState::Game), reset_progress)
+            .add_systems(
+                FixedUpdate,
+                play_stage
+                    .in_set(GameplaySet::Spawn)
+                    .before(spawn_requested_aliens)
+                    .run_if(in_state(AppState::Game)),
+            )
+            .add_systems(Update, fade_banner.run_if(in_state(AppState::Game)))
+            .add_systems(OnExit(AppState::Game), despawn_banner);
+    }
+}
+
+fn load_stages(asset_server: Res<AssetServer>, mut library: ResMut<StageLibrary>) {
+    library.stages = STAGES.iter().map(|path| asset_server.load(*path)).collect();
+}
+
+/// Leaves `AppState::Loading` once every stage has loaded, going straight
+/// into a round when a replay is waiting to be played.
+///
+/// If any stage fails the game stays here and says which, since playing on
+/// without it would change every later stage.
+fn finish_loading(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    library: Res<StageLibrary>,
+    playback: Option<Res<ReplayPlayback>>,
+    error_query: Query<(), With<LoadingError>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let mut done = true;
+    let mut failed = Vec::new();
+    for (handle, path) in library.stages.iter().zip(STAGES) {
+        match asset_server.load_state(handle) {
+            LoadState::Loaded => {}
+            LoadState::Failed => failed.push(path),
+            LoadState::NotLoaded | LoadState::Loading => done = false,
+        }
+    }
+
+    if !failed.is_empty() {
+        if error_query.is_empty() {
+            for path in failed.iter() {
+                error!("Could not load stage {path}");
+            }
+            spawn_loading_error(&mut commands, &asset_server, &failed);
+        }
+        return;
+    }
+    if done {
+        next_state.set(if playback.is_some() {
+            AppState::Game
+        } else {
+            AppState::IntroScreen
+        });
+    }
+}
+
+fn spawn_loading_error(commands: &mut Commands, asset_server: &AssetServer, failed: &[&str]) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Orbitron-VariableFont_wght.ttf"),
+        font_size: 30.0,
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            LoadingError,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    format!("COULD NOT LOAD\n{}", failed.join("\n")),
+                    text_style,
+                )
+                .with_text_justify(JustifyText::Center),
+            );
+        });
+}
+
+fn reset_progress(mut progress: ResMut<StageProgress>) {
+    *progress = StageProgress::default();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_stage(
+    mut commands: Commands,
+    time: Res<Time>,
+    bounds: Res<PlayfieldBounds>,
+    library: Res<StageLibrary>,
+    stages: Res<Assets<Stage>>,
+    mut progress: ResMut<StageProgress>,
+    aliens_query: Query<(), With<Alien>>,
+    mut spawn_events: EventWriter<SpawnAlien>,
+    mut boss_events: EventWriter<SpawnBoss>,
+    asset_server: Res<AssetServer>,
+) {
+    progress.clock += time.delta_seconds();
+    // Aliens sent this tick are not spawned yet, so a wave they belong to
+    // cannot count as cleared until the next tick.
+    let mut spawned = false;
+    loop {
+        // Every stage loaded before the round could start.
+        let handle = &library.stages[progress.stage % library.stages.len()];
+        let Some(stage) = stages.get(handle) else {
+            return;
+        };
+        let Some(event) = stage.events.get(progress.next_event) else {
+            if aliens_query.is_empty() && !spawned {
+                progress.stage += 1;
+                progress.next_event = 0;
+                progress.clock = 0.0;
+            }
+            return;
+        };
+        if event.at > progress.clock {
+            return;
+        }
+        if progress.next_event == 0 {
+            info!("Starting {}", stage.name);
+        }
+
+        match &event.action {
+            StageAction::Spawn {
+                kind,
+                count,
+                formation,
+                y,
+            } => {
+                let leader = Vec2::new(bounds.width / 2.0, y * bounds.height / 2.0);
+                for index in 0..*count {
+                    spawn_events.send(SpawnAlien {
+                        kind: *kind,
+                        position: leader + formation.offset(index, *count),
+                    });
+                }
+                spawned = true;
+            }
+            StageAction::WaitUntilClear => {
+                if spawned || !aliens_query.is_empty() {
+                    // Hold the clock here until the wave is gone.
+                    progress.clock = event.at;
+                    return;
+                }
+            }
+            StageAction::Banner { text, seconds } => {
+                spawn_banner(&mut commands, &asset_server, text, *seconds);
+            }
+            StageAction::Boss => {
+                boss_events.send(SpawnBoss);
+                spawned = true;
+            }
+        }
+        progress.next_event += 1;
+    }
+}
+
+fn spawn_banner(commands: &mut Commands, asset_server: &AssetServer, text: &str, seconds: f32) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Orbitron-VariableFont_wght.ttf"),
+        font_size: 60.0,
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            StageBanner { remaining: seconds },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(text, text_style));
+        });
+}
+
+fn fade_banner(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut StageBanner)>,
+) {
+    for (entity, mut banner) in query.iter_mut() {
+        banner.remaining -= time.delta_seconds();
+        if banner.remaining <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn despawn_banner(mut commands: Commands, query: Query<Entity, With<StageBanner>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,248 @@
+use std::{error::Error, fmt};
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Somewhere to keep small named documents between sessions.
+pub trait StorageBackend: Send + Sync + 'static {
+    fn read(&self, key: &str) -> Result<Option<String>, StorageError>;
+    fn write(&mut self, key: &str, contents: &str) -> Result<(), StorageError>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageError(String);
+
+impl StorageError {
+    /// For backends to report what went wrong.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for StorageError {}
+
+/// Persistent storage for high scores, settings and statistics.
+///
+/// Values are stored as RON under a short key such as `"highscores"`.
+#[derive(Resource)]
+pub struct Storage {
+    backend: Box<dyn StorageBackend>,
+}
+
+impl Storage {
+    pub fn new(backend: impl StorageBackend) -> Self {
+        Self {
+            backend: Box::new(backend),
+        }
+    }
+
+    pub fn in_memory() -> Self {
+        Self::new(MemoryStorage::default())
+    }
+
+    /// Files under the user's data directory on native builds,
+    /// `localStorage` on the web.
+    pub fn platform_default() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        match FileStorage::in_data_dir() {
+            Some(storage) => Self::new(storage),
+            None => {
+                warn!("No data directory found, nothing will be saved");
+                Self::in_memory()
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        Self::new(LocalStorage)
+    }
+
+    /// Returns `None` if nothing is stored under `key` or it cannot be read.
+    pub fn load<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let contents = match self.backend.read(key) {
+            Ok(contents) => contents?,
+            Err(err) => {
+                error!("Could not read {key}: {err}");
+                return None;
+            }
+        };
+        ron::from_str(&contents)
+            .map_err(|err| warn!("Ignoring unreadable {key}: {err}"))
+            .ok()
+    }
+
+    pub fn save<T: Serialize>(&mut self, key: &str, value: &T) {
+        let result = ron::ser::to_string_pretty(value, default())
+            .map_err(|err| StorageError(err.to_string()))
+            .and_then(|contents| self.backend.write(key, &contents));
+        if let Err(err) = result {
+            error!("Could not save {key}: {err}");
+        }
+    }
+}
+
+/// Keeps everything in memory for the lifetime of the app.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    values: HashMap<String, String>,
+}
+
+impl StorageBackend for MemoryStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, StorageError> {
+        Ok(self.values.get(key).cloned())
+    }
+
+    fn write(&mut self, key: &str, contents: &str) -> Result<(), StorageError> {
+        self.values.insert(key.to_string(), contents.to_string());
+        Ok(())
+    }
+}
+
+/// One `<key>.ron` file per key in a directory.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct FileStorage {
+    directory: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    pub fn new(directory: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    pub fn in_data_dir() -> Option<Self> {
+        dirs::data_dir().map(|dir| Self::new(dir.join("alien_space_shooter")))
+    }
+
+    fn path(&self, key: &str) -> std::path::PathBuf {
+        self.directory.join(format!("{key}.ron"))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl StorageBackend for FileStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, StorageError> {
+        match std::fs::read_to_string(self.path(key)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(StorageError(err.to_string())),
+        }
+    }
+
+    fn write(&mut self, key: &str, contents: &str) -> Result<(), StorageError> {
+        std::fs::create_dir_all(&self.directory)
+            .and_then(|()| std::fs::write(self.path(key), contents))
+            .map_err(|err| StorageError(err.to_string()))
+    }
+}
+
+/// The browser's `localStorage`, with keys prefixed by the game's name.
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug)]
+pub struct LocalStorage;
+
+#[cfg(target_arch = "wasm32")]
+impl LocalStorage {
+    fn storage() -> Result<web_sys::Storage, StorageError> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| StorageError("localStorage is not available".to_string()))
+    }
+
+    fn item(key: &str) -> String {
+        format!("alien_space_shooter.{key}")
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl StorageBackend for LocalStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, StorageError> {
+        Self::storage()?
+            .get_item(&Self::item(key))
+            .map_err(|err| StorageError(format!("{err:?}")))
+    }
+
+    fn write(&mut self, key: &str, contents: &str) -> Result<(), StorageError> {
+        Self::storage()?
+            .set_item(&Self::item(key), contents)
+            .map_err(|err| StorageError(format!("{err:?}")))
+    }
+}
+
+/// Inserts the platform's default [`Storage`] unless one was provided.
+pub struct StoragePlugin;
+
+impl Plugin for StoragePlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<Storage>() {
+            app.insert_resource(Storage::platform_default());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A backend that can neither read nor write.
+    struct BrokenStorage;
+
+    impl StorageBackend for BrokenStorage {
+        fn read(&self, _key: &str) -> Result<Option<String>, StorageError> {
+            Err(StorageError::new("broken"))
+        }
+
+        fn write(&mut self, _key: &str, _contents: &str) -> Result<(), StorageError> {
+            Err(StorageError::new("broken"))
+        }
+    }
+
+    #[test]
+    fn memory_storage_reads_back_what_was_written() {
+        let mut backend = MemoryStorage::default();
+        assert_eq!(backend.read("settings"), Ok(None));
+
+        backend.write("settings", "first").unwrap();
+        backend.write("settings", "second").unwrap();
+        backend.write("other", "third").unwrap();
+        assert_eq!(backend.read("settings"), Ok(Some("second".to_string())));
+        assert_eq!(backend.read("other"), Ok(Some("third".to_string())));
+    }
+
+    #[test]
+    fn values_round_trip_through_ron() {
+        let mut storage = Storage::in_memory();
+        let value = (42_u32, "name".to_string(), vec![1.5_f32, -2.0]);
+        storage.save("value", &value);
+        assert_eq!(storage.load("value"), Some(value));
+    }
+
+    #[test]
+    fn missing_keys_load_as_none() {
+        let storage = Storage::in_memory();
+        assert_eq!(storage.load::<u32>("missing"), None);
+    }
+
+    #[test]
+    fn unreadable_values_load_as_none() {
+        let mut backend = MemoryStorage::default();
+        backend.write("value", "not ron at all (").unwrap();
+        let storage = Storage::new(backend);
+        assert_eq!(storage.load::<u32>("value"), None);
+    }
+
+    #[test]
+    fn backend_errors_are_not_fatal() {
+        let mut storage = Storage::new(BrokenStorage);
+        storage.save("value", &1_u32);
+        assert_eq!(storage.load::<u32>("value"), None);
+    }
+}
@@ -0,0 +1,329 @@
+//! On-screen controls for phones and tablets.
+//!
+//! During a round a touch that starts on the left half of the screen plants
+//! a virtual joystick where the finger landed, and the buttons in the
+//! bottom right corner fire and drop bombs. On the title and high score
+//! screens a tap anywhere but on a button confirms. The controls only show
+//! once the screen has been touched.
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    actions::{Action, ActionSet, ActionState, InputDevice},
+    AppState,
+};
+
+/// How far the finger has to move from where it landed for full speed.
+const STICK_RADIUS: f32 = 70.0;
+const KNOB_SIZE: f32 = 50.0;
+const IDLE_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.15);
+const HELD_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.35);
+
+/// A round button, placed by the distance from a right hand corner of the
+/// window to its centre.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+struct TouchButton {
+    action: Action,
+    from_corner: Vec2,
+    radius: f32,
+    /// Measured from the top right corner rather than the bottom right.
+    top: bool,
+}
+
+const TOUCH_BUTTONS: [TouchButton; 3] = [
+    TouchButton {
+        action: Action::Fire,
+        from_corner: Vec2::new(110.0, 110.0),
+        radius: 70.0,
+        top: false,
+    },
+    TouchButton {
+        action: Action::Bomb,
+        from_corner: Vec2::new(250.0, 80.0),
+        radius: 45.0,
+        top: false,
+    },
+    TouchButton {
+        action: Action::Pause,
+        from_corner: Vec2::new(60.0, 60.0),
+        radius: 35.0,
+        top: true,
+    },
+];
+
+impl TouchButton {
+    /// Centre in window coordinates, with y down.
+    fn centre(&self, window_size: Vec2) -> Vec2 {
+        if self.top {
+            Vec2::new(window_size.x - self.from_corner.x, self.from_corner.y)
+        } else {
+            window_size - self.from_corner
+        }
+    }
+
+    fn contains(&self, window_size: Vec2, point: Vec2) -> bool {
+        self.centre(window_size).distance(point) <= self.radius
+    }
+}
+
+/// What the on-screen controls show for an action.
+pub fn touch_label(action: Action) -> &'static str {
+    match action {
+        Action::Fire => "FIRE",
+        Action::Bomb => "BOMB",
+        Action::Pause => "II",
+        _ => "TAP",
+    }
+}
+
+/// The finger steering with the virtual joystick, if any.
+#[derive(Resource, Debug, Default)]
+struct TouchStick {
+    finger: Option<u64>,
+    origin: Vec2,
+    position: Vec2,
+}
+
+#[derive(Component, Debug)]
+struct TouchOverlay;
+
+#[derive(Component, Debug)]
+struct StickBase;
+
+#[derive(Component, Debug)]
+struct StickKnob;
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchStick>()
+            .add_systems(PreUpdate, read_touches.in_set(ActionSet))
+            .add_systems(OnEnter(AppState::Game), spawn_overlay)
+            .add_systems(Update, update_overlay.run_if(in_state(AppState::Game)))
+            .add_systems(OnExit(AppState::Game), despawn_overlay);
+    }
+}
+
+fn read_touches(
+    touches: Res<Touches>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    ui_buttons_query: Query<(&Node, &GlobalTransform, &ViewVisibility), With<Button>>,
+    app_state: Res<State<AppState>>,
+    mut stick: ResMut<TouchStick>,
+    mut state: ResMut<ActionState>,
+    mut device: ResMut<InputDevice>,
+) {
+    if touches.any_just_pressed() {
+        device.set_if_neq(InputDevice::Touch);
+    }
+
+    match app_state.get() {
+        AppState::IntroScreen | AppState::Highscores => {
+            // Buttons on these screens handle their own taps.
+            let on_ui_button = |position: Vec2| {
+                ui_buttons_query
+                    .iter()
+                    .any(|(node, transform, visibility)| {
+                        visibility.get() && node.logical_rect(transform).contains(position)
+                    })
+            };
+            if touches
+                .iter_just_pressed()
+                .any(|touch| !on_ui_button(touch.position()))
+            {
+                state.press(Action::Confirm);
+            }
+            return;
+        }
+        AppState::Game => {}
+        // The other screens are worked by tapping their buttons.
+        _ => return,
+    }
+
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+
+    if let Some(finger) = stick.finger {
+        match touches.get_pressed(finger) {
+            Some(touch) => stick.position = touch.position(),
+            None => stick.finger = None,
+        }
+    }
+
+    for touch in touches.iter() {
+        let position = touch.position();
+        let mut on_button = false;
+        for button in TOUCH_BUTTONS {
+            if button.contains(window_size, position) {
+                state.press(button.action);
+                on_button = true;
+            }
+        }
+
+        let starts_stick = stick.finger.is_none()
+            && !on_button
+            && touches.just_pressed(touch.id())
+            && position.x < window_size.x / 2.0;
+        if starts_stick {
+            *stick = TouchStick {
+                finger: Some(touch.id()),
+                origin: position,
+                position,
+            };
+        }
+    }
+
+    if stick.finger.is_some() {
+        // Window coordinates have y pointing down.
+        let offset = (stick.position - stick.origin) / STICK_RADIUS;
+        state.push_axis(Vec2::new(offset.x, -offset.y).clamp_length_max(1.0));
+    }
+}
+
+fn spawn_overlay(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Orbitron-VariableFont_wght.ttf"),
+        font_size: 20.0,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            TouchOverlay,
+        ))
+        .with_children(|parent| {
+            for button in TOUCH_BUTTONS {
+                let size = Val::Px(button.radius * 2.0);
+                let edge = Val::Px(button.from_corner.y - button.radius);
+                parent
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                width: size,
+                                height: size,
+                                right: Val::Px(button.from_corner.x - button.radius),
+                                top: if button.top { edge } else { Val::Auto },
+                                bottom: if button.top { Val::Auto } else { edge },
+                                align_items: AlignItems::Center,
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            background_color: IDLE_COLOR.into(),
+                            ..default()
+                        },
+                        button,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            touch_label(button.action),
+                            text_style.clone(),
+                        ));
+                    });
+            }
+
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(STICK_RADIUS * 2.0),
+                        height: Val::Px(STICK_RADIUS * 2.0),
+                        ..default()
+                    },
+                    background_color: IDLE_COLOR.into(),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                StickBase,
+            ));
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(KNOB_SIZE),
+                        height: Val::Px(KNOB_SIZE),
+                        ..default()
+                    },
+                    background_color: HELD_COLOR.into(),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                StickKnob,
+            ));
+        });
+}
+
+/// Shows the controls once the screen has been touched, with the joystick
+/// under the steering finger.
+#[allow(clippy::type_complexity)]
+fn update_overlay(
+    device: Res<InputDevice>,
+    stick: Res<TouchStick>,
+    actions: Res<ActionState>,
+    mut overlay_query: Query<&mut Visibility, With<TouchOverlay>>,
+    mut buttons_query: Query<(&TouchButton, &mut BackgroundColor)>,
+    mut base_query: Query<(&mut Style, &mut Visibility), (With<StickBase>, Without<TouchOverlay>)>,
+    mut knob_query: Query<
+        (&mut Style, &mut Visibility),
+        (With<StickKnob>, Without<StickBase>, Without<TouchOverlay>),
+    >,
+) {
+    for mut visibility in overlay_query.iter_mut() {
+        *visibility = if *device == InputDevice::Touch {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    for (button, mut background) in buttons_query.iter_mut() {
+        *background = if actions.pressed(button.action) {
+            HELD_COLOR
+        } else {
+            IDLE_COLOR
+        }
+        .into();
+    }
+
+    let steering = stick.finger.is_some();
+    let knob = stick.origin + (stick.position - stick.origin).clamp_length_max(STICK_RADIUS);
+    for (centre, size, node) in [
+        (
+            stick.origin,
+            STICK_RADIUS * 2.0,
+            base_query.get_single_mut(),
+        ),
+        (knob, KNOB_SIZE, knob_query.get_single_mut()),
+    ] {
+        let Ok((mut style, mut visibility)) = node else {
+            continue;
+        };
+        *visibility = if steering {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        style.left = Val::Px(centre.x - size / 2.0);
+        style.top = Val::Px(centre.y - size / 2.0);
+    }
+}
+
+fn despawn_overlay(mut commands: Commands, query: Query<Entity, With<TouchOverlay>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+
+pub const MAX_WEAPON_LEVEL: u32 = 3;
+
+/// The guns the player's ship can be fitted with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeaponKind {
+    /// Straight shots, side by side as it levels up. What every ship starts with.
+    Blaster,
+    /// A fan of shots that widens as it levels up.
+    Spread,
+    /// Two fast lasers that hit harder as it levels up.
+    TwinLaser,
+    /// Fires forwards and backwards.
+    RearGun,
+}
+
+impl WeaponKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeaponKind::Blaster => "BLASTER",
+            WeaponKind::Spread => "SPREAD",
+            WeaponKind::TwinLaser => "LASER",
+            WeaponKind::RearGun => "REAR",
+        }
+    }
+}
+
+/// What a shot looks like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projectile {
+    Bullet,
+    /// A small round shot, for volleys that fan out.
+    Pellet,
+    Laser,
+}
+
+impl Projectile {
+    pub const ALL: [Projectile; 3] = [Projectile::Bullet, Projectile::Pellet, Projectile::Laser];
+
+    /// The sprite, relative to the assets directory.
+    pub fn image_path(&self) -> &'static str {
+        match self {
+            Projectile::Bullet => "playerBullet.png",
+            Projectile::Pellet => "spreadShot.png",
+            Projectile::Laser => "laserBeam.png",
+        }
+    }
+
+    /// Size of the sprite in pixels, which the shot's collider fits.
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Projectile::Bullet => (26, 9),
+            Projectile::Pellet => (12, 12),
+            Projectile::Laser => (52, 5),
+        }
+    }
+}
+
+/// How the player's ship fires. Replaced or levelled up by pickups and lost
+/// with the ship.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Weapon {
+    pub kind: WeaponKind,
+    pub level: u32,
+    /// Frames between volleys.
+    pub reload: f32,
+    pub projectile: Projectile,
+    pub damage: u32,
+    pub speed: f32,
+    /// Shots in each volley, not counting the rear guns.
+    pub count: u32,
+    /// Radians between neighbouring shots.
+    pub spread: f32,
+    /// Vertical distance between neighbouring shots where they leave the ship.
+    pub gap: f32,
+    /// Fires the same volley backwards as well.
+    pub rear: bool,
+}
+
+impl Weapon {
+    pub fn new(kind: WeaponKind, level: u32) -> Self {
+        let level = level.clamp(1, MAX_WEAPON_LEVEL);
+        let blaster = Self {
+            kind,
+            level,
+            reload: 12.0,
+            projectile: Projectile::Bullet,
+            damage: 1,
+            speed: 500.0,
+            count: 1,
+            spread: 0.0,
+            gap: 0.0,
+            rear: false,
+        };
+        match kind {
+            WeaponKind::Blaster => Self {
+                count: level,
+                gap: 10.0,
+                ..blaster
+            },
+            WeaponKind::Spread => Self {
+                reload: 14.0,
+                projectile: Projectile::Pellet,
+                count: 1 + 2 * level,
+                spread: 0.15,
+                ..blaster
+            },
+            WeaponKind::TwinLaser => Self {
+                reload: 10.0,
+                projectile: Projectile::Laser,
+                damage: level,
+                speed: 800.0,
+                count: 2,
+                gap: 16.0,
+                ..blaster
+            },
+            WeaponKind::RearGun => Self {
+                count: level,
+                gap: 10.0,
+                rear: true,
+                ..blaster
+            },
+        }
+    }
+
+    /// The weapon a pickup of `kind` turns this one into: the next level of
+    /// the same weapon, or the new weapon at the current level.
+    pub fn picked_up(&self, kind: WeaponKind) -> Self {
+        if kind == self.kind {
+            Self::new(kind, self.level + 1)
+        } else {
+            Self::new(kind, self.level)
+        }
+    }
+
+    /// Where each shot of a volley starts relative to the ship, and which way
+    /// it goes.
+    pub fn volley(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        let forward = (0..self.count).map(move |index| {
+            let side = index as f32 - (self.count - 1) as f32 / 2.0;
+            (
+                Vec2::new(0.0, side * self.gap),
+                Vec2::from_angle(side * self.spread),
+            )
+        });
+        let backward = forward
+            .clone()
+            .filter(move |_| self.rear)
+            .map(|(offset, direction)| (offset, -direction));
+        forward.chain(backward)
+    }
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Self::new(WeaponKind::Blaster, 1)
+    }
+}